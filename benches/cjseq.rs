@@ -0,0 +1,59 @@
+use cjval::{CJValidator, SchemaSet};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+
+fn validate_stream(lines: &[&str], ext: Option<&str>, schemas: Option<Arc<SchemaSet>>) {
+    //-- without a shared SchemaSet, every validation recompiles the schemas
+    let fresh = || {
+        schemas
+            .clone()
+            .unwrap_or_else(|| Arc::new(SchemaSet::new()))
+    };
+    let mut val = CJValidator::from_str_with_schemas(lines[0], fresh());
+    if let Some(e) = ext {
+        val.add_one_extension_from_str(e).unwrap();
+    }
+    let _ = val.validate();
+    for l in &lines[1..] {
+        let mut valf = CJValidator::from_str_with_schemas(lines[0], fresh());
+        if let Some(e) = ext {
+            valf.add_one_extension_from_str(e).unwrap();
+        }
+        let _ = valf.from_str_cjfeature(l);
+        let _ = valf.validate();
+    }
+}
+
+fn bench_cjseq(c: &mut Criterion) {
+    let s = std::fs::read_to_string("data/cube_20.jsonl").unwrap();
+    let lines: Vec<&str> = s.lines().filter(|l| !l.is_empty()).collect();
+    let se = std::fs::read_to_string("data/extension.jsonl").unwrap();
+    let lines_ext: Vec<&str> = se.lines().filter(|l| !l.is_empty()).collect();
+    let noise = std::fs::read_to_string("schemas/extensions/20/noise.ext.json").unwrap();
+
+    let shared = Arc::new(SchemaSet::new());
+    shared.precompile();
+
+    let mut g = c.benchmark_group("cube_20.jsonl");
+    g.sample_size(10);
+    g.bench_function("compile per feature", |b| {
+        b.iter(|| validate_stream(&lines, None, None))
+    });
+    g.bench_function("shared SchemaSet", |b| {
+        b.iter(|| validate_stream(&lines, None, Some(shared.clone())))
+    });
+    g.finish();
+
+    let mut g = c.benchmark_group("extension.jsonl");
+    g.sample_size(10);
+    g.bench_function("compile per feature", |b| {
+        b.iter(|| validate_stream(&lines_ext, Some(&noise), None))
+    });
+    g.bench_function("shared SchemaSet", |b| {
+        b.iter(|| validate_stream(&lines_ext, Some(&noise), Some(shared.clone())))
+    });
+    g.finish();
+}
+
+criterion_group!(benches, bench_cjseq);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+#![no_main]
+//! A CityJSONSeq stream: the 1st line is the CityJSON object, the others
+//! the CityJSONFeature (validated in the exhaustive mode)
+
+use cjval::{CJValidator, ValidationOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut lines = s.lines();
+    let Some(first) = lines.next() else {
+        return;
+    };
+    let mut v = CJValidator::from_str(first);
+    let mut opts = ValidationOptions::new();
+    opts.set_exhaustive(true);
+    v.set_options(opts);
+    let _ = v.validate();
+    for (i, l) in lines.enumerate() {
+        if v.from_str_cjfeature_at(l, i + 2).is_ok() {
+            let _ = v.get_cjseq_feature_id();
+            let _ = v.is_empty_cityjson();
+            let _ = v.validate();
+        }
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+//! An Extension file, validated alone and used to validate a CityJSON file
+//! (the input is split at the first NUL byte: the Extension, then the file)
+
+use cjval::CJValidator;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (sext, sdata) = s.split_once('\0').unwrap_or((s, ""));
+    let _ = cjval::validate_extension_str(sext);
+    let _ = cjval::diff_extensions_str(sext, sext);
+    let mut v = CJValidator::from_str(sdata);
+    if v.add_one_extension_from_str(sext).is_ok() {
+        let _ = v.get_extension_definitions();
+        let _ = v.validate();
+    }
+});
@@ -0,0 +1,22 @@
+#![no_main]
+//! A CityJSON file, validated with all the getters called, and with all the
+//! checks performed even after errors (the exhaustive mode)
+
+use cjval::{CJValidator, ValidationOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let mut v = CJValidator::from_str(s);
+        let _ = v.get_extensions_urls();
+        let _ = v.get_extensions();
+        let _ = v.is_empty_cityjson();
+        let _ = v.get_cjseq_feature_id();
+        let _ = v.validate();
+        let mut opts = ValidationOptions::new();
+        opts.set_exhaustive(true);
+        v.set_options(opts);
+        let _ = v.validate();
+    }
+    let _ = CJValidator::from_reader(data).validate();
+});
@@ -0,0 +1,161 @@
+//! Baselines: the known issues of a file that are accepted, they are not
+//! reported as errors (or warnings) anymore, only the new ones are.
+//!
+//! A baseline is a JSON file, it can be written from the results of a
+//! validation (`cjval --write-baseline`) and edited:
+//!
+//! ```json
+//! {
+//!   "ignore": [
+//!     {"check": "duplicate_vertices"},
+//!     {"check": "wrong_vertex_index", "cityobject": "id-1"},
+//!     {"check": "schema", "pointer": "/CityObjects/id-2/geometry/0"},
+//!     {"check": "materials", "message": "Material \"value\" overflow; #id-3 / geom-#0 / material-\"irradiation\""}
+//!   ]
+//! }
+//! ```
+//!
+//! An entry ignores the errors of the check that have all the properties
+//! given: the id of the CityObject, the JSON Pointer (the value or one of its
+//! children), and the message.
+
+use crate::{Diagnostic, ValSummary};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// The known issues to ignore, see the module documentation for the format
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    #[serde(default)]
+    pub ignore: Vec<BaselineEntry>,
+}
+
+/// One known issue (or a group of issues)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BaselineEntry {
+    /// The name of the check (eg "duplicate_vertices")
+    pub check: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cityobject: Option<String>,
+    /// A JSON Pointer, the errors for that value and its children are ignored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl BaselineEntry {
+    /// The entry that ignores exactly that diagnostic
+    pub fn from_diagnostic(check: &str, d: &Diagnostic) -> Self {
+        BaselineEntry {
+            check: check.to_string(),
+            cityobject: d.cityobject.clone(),
+            pointer: d.pointer.clone(),
+            message: Some(d.message.clone()),
+        }
+    }
+
+    /// Returns true if the diagnostic of the check is ignored by the entry
+    pub fn matches(&self, check: &str, d: &Diagnostic) -> bool {
+        self.check == check
+            && self
+                .cityobject
+                .as_ref()
+                .map_or(true, |id| d.cityobject.as_ref() == Some(id))
+            && self.pointer.as_ref().map_or(true, |p| {
+                d.pointer
+                    .as_ref()
+                    .is_some_and(|dp| dp == p || dp.starts_with(&format!("{}/", p)))
+            })
+            && self.message.as_ref().map_or(true, |m| *m == d.message)
+    }
+}
+
+impl Baseline {
+    pub fn new() -> Self {
+        Baseline::default()
+    }
+
+    /// Reads a baseline from a JSON string
+    /// ```rust
+    /// let s = r#"{"ignore": [{"check": "duplicate_vertices"}]}"#;
+    /// let b = cjval::Baseline::from_json_str(s).unwrap();
+    /// assert_eq!(b.len(), 1);
+    /// ```
+    pub fn from_json_str(s: &str) -> Result<Self, String> {
+        serde_json::from_str(s).map_err(|e| e.to_string())
+    }
+
+    /// The baseline as a (pretty) JSON string
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Adds all the errors and warnings of the results of
+    /// [`crate::CJValidator::validate`] (also those already ignored), to
+    /// accept them. Can be called for each feature of a CityJSONSeq.
+    pub fn add_results(&mut self, re: &IndexMap<String, ValSummary>) {
+        let mut existing: HashSet<BaselineEntry> = self.ignore.iter().cloned().collect();
+        for (check, summ) in re {
+            for d in summ.get_diagnostics().iter().chain(summ.get_ignored()) {
+                let e = BaselineEntry::from_diagnostic(check, d);
+                if existing.insert(e.clone()) {
+                    self.ignore.push(e);
+                }
+            }
+        }
+    }
+
+    /// Returns true if the diagnostic of the check is ignored
+    pub fn is_ignored(&self, check: &str, d: &Diagnostic) -> bool {
+        self.ignore.iter().any(|e| e.matches(check, d))
+    }
+
+    /// The number of entries
+    pub fn len(&self) -> usize {
+        self.ignore.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ignore.is_empty()
+    }
+}
+
+/// A baseline indexed by check and message, for large baselines (eg written
+/// with `cjval --write-baseline` for a file with many errors)
+pub(crate) struct BaselineIndex<'a> {
+    with_message: HashMap<(&'a str, &'a str), Vec<&'a BaselineEntry>>,
+    others: Vec<&'a BaselineEntry>,
+}
+
+impl<'a> BaselineIndex<'a> {
+    pub(crate) fn new(baseline: &'a Baseline) -> Self {
+        let mut bi = BaselineIndex {
+            with_message: HashMap::new(),
+            others: Vec::new(),
+        };
+        for e in &baseline.ignore {
+            match &e.message {
+                Some(m) => bi
+                    .with_message
+                    .entry((e.check.as_str(), m.as_str()))
+                    .or_default()
+                    .push(e),
+                None => bi.others.push(e),
+            }
+        }
+        bi
+    }
+
+    pub(crate) fn is_ignored(&self, check: &str, d: &Diagnostic) -> bool {
+        self.with_message
+            .get(&(check, d.message.as_str()))
+            .into_iter()
+            .flatten()
+            .chain(&self.others)
+            .any(|e| e.matches(check, d))
+    }
+}
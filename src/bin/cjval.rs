@@ -1,17 +1,20 @@
 use cjval::CJValidator;
+use cjval::Equivalence;
+use cjval::HttpResolver;
 use cjval::ValSummary;
+use cjval::{Baseline, Profile, Severity, ValidationOptions};
 use indexmap::IndexMap;
 
 extern crate clap;
 
 use std::collections::HashMap;
-use std::io::{self, BufRead};
-use std::path::PathBuf;
-use url::Url;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
@@ -27,6 +30,9 @@ use ratatui::{
     Frame, Terminal,
 };
 
+/// The number of different errors listed for each check, by default
+const DEFAULT_ERROR_LIMIT: usize = 100;
+
 #[derive(Parser)]
 #[command(
     about = "Schema-validation of CityJSON/Seq datasets",
@@ -35,21 +41,122 @@ use ratatui::{
     long_about = None
 )]
 struct Cli {
-    /// CityJSON input file
+    #[command(subcommand)]
+    command: Option<Commands>,
+    /// CityJSON input file (or CityJSONSeq if .jsonl),
+    /// can be compressed with gzip or zstd
     inputfile: Option<PathBuf>,
     /// Quiet mode, the TUI (with the details) is not shown
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     quiet: bool,
     /// Read the CityJSON Extensions files locally instead of downloading them.
     /// More than one can be given.
     #[arg(short, long)]
     extensionfiles: Vec<PathBuf>,
+    /// CityJSONSeq: start validating the features at line N
+    /// (the 1st line is always validated)
+    #[arg(long, value_name = "N")]
+    start_line: Option<usize>,
+    /// CityJSONSeq: stop after M features have been validated
+    #[arg(long, value_name = "M")]
+    max_features: Option<usize>,
+    /// CityJSONSeq: validate only every k-th feature
+    #[arg(long, value_name = "K")]
+    every: Option<usize>,
+    /// CityJSONSeq: validate only a random sample of the features
+    /// (fraction between 0 and 1, eg 0.01 for 1%)
+    #[arg(long, value_name = "FRACTION")]
+    sample: Option<f64>,
+    /// CityJSONSeq: seed for the random sample (to reproduce a run)
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+    /// CityJSONSeq: stop after K invalid features
+    #[arg(long, value_name = "K")]
+    max_errors: Option<usize>,
+    /// Compare the CityJSON input file with a CityJSONSeq (use '-' for stdin),
+    /// to check that they contain the same CityObjects
+    #[arg(long, value_name = "CJSEQ")]
+    compare_seq: Option<PathBuf>,
+    /// Rule set (TOML file) to disable checks or change their severity,
+    /// "cjval.toml" in the current directory is used if it exists
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Report these checks as errors (eg 'duplicate_vertices')
+    #[arg(long, value_name = "CHECK", value_delimiter = ',')]
+    error: Vec<String>,
+    /// Report these checks as warnings
+    #[arg(long, value_name = "CHECK", value_delimiter = ',')]
+    warning: Vec<String>,
+    /// Do not perform these checks (eg 'unused_vertices')
+    #[arg(long, value_name = "CHECK", value_delimiter = ',')]
+    disable: Vec<String>,
+    /// Perform all the checks, even after errors (the errors that may be
+    /// caused by earlier ones are marked)
+    #[arg(long)]
+    exhaustive: bool,
+    /// List only the first N different errors of each check, the others are
+    /// counted [default: 100]
+    #[arg(long, value_name = "N")]
+    error_limit: Option<usize>,
+    /// List all the errors (no limit per check)
+    #[arg(long, conflicts_with = "error_limit")]
+    all_errors: bool,
+    /// Validation profile (JSON or TOML file) with data-quality requirements
+    /// (required geometries/LoDs, attributes, etc.)
+    #[arg(long, value_name = "FILE")]
+    profile: Option<PathBuf>,
+    /// JSON Schemas for the attributes of the CityObjects, a JSON file with
+    /// the CityObject types as keys (eg {"Building": {"required": ["status"]}})
+    #[arg(long, value_name = "FILE")]
+    attribute_schemas: Option<PathBuf>,
+    /// Known issues to ignore (JSON file), only the new ones are reported
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+    /// Write all the errors and warnings found to a baseline file, to
+    /// ignore them in the next runs (with --baseline); all the errors are
+    /// listed
+    #[arg(long, value_name = "FILE")]
+    write_baseline: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Validate a CityJSON Extension file (.ext.json)
+    Extension {
+        /// CityJSON Extension file
+        inputfile: PathBuf,
+    },
+    /// Compare two versions of an Extension: are the files valid with the
+    /// old version still valid with the new one?
+    ExtensionDiff {
+        /// The old version of the Extension file
+        old: PathBuf,
+        /// The new version of the Extension file
+        new: PathBuf,
+    },
+}
+
+/// Which features of a CityJSONSeq stream are validated
+struct SeqOptions {
+    start_line: usize,
+    max_features: Option<usize>,
+    every: usize,
+    sample: Option<f64>,
+    seed: u64,
+    max_errors: Option<usize>,
 }
 
 struct ValidationResult {
     file_path: String,
     schema_version: String,
     extensions: Vec<(String, String)>,
+    /// The rule set used (None for Extension files)
+    rules: Option<String>,
+    /// The number of errors/warnings ignored because of the baseline
+    ignored: usize,
+    /// The number of errors/warnings (also those grouped or not listed)
+    nerrors: usize,
+    nwarnings: usize,
     errors: Vec<(String, Vec<String>)>,
     warnings: Vec<(String, Vec<String>)>,
     validity: Validity,
@@ -65,50 +172,307 @@ enum Validity {
 fn main() {
     let cli = Cli::parse();
 
-    match cli.inputfile {
+    match &cli.command {
+        Some(Commands::Extension { inputfile }) => {
+            match validate_extension_file(inputfile) {
+                Ok(vr) => show_result(vr, cli.quiet),
+                Err(e) => {
+                    eprintln!("ERROR: cannot read {}: {}", inputfile.display(), e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Commands::ExtensionDiff { old, new }) => {
+            diff_extension_files(old, new);
+            return;
+        }
+        None => (),
+    }
+
+    match &cli.inputfile {
         Some(ifile) => {
             if !ifile.exists() {
                 eprintln!("ERROR: Input file {} doesn't exist", ifile.display());
                 std::process::exit(1);
             }
-            let fext = ifile.extension().unwrap().to_str().unwrap();
-            match fext {
+            let fext = get_input_extension(ifile);
+            match fext.as_str() {
+                "json" | "JSON" if cli.compare_seq.is_some() => {
+                    compare_cityjson_cjseq(ifile, cli.compare_seq.as_ref().unwrap());
+                }
                 "json" | "JSON" => {
-                    let result = validate_cityjson_file(&ifile, &cli.extensionfiles);
+                    let result =
+                        validate_cityjson_file(ifile, &cli.extensionfiles, get_rule_set(&cli));
                     match result {
-                        Ok(vr) => {
-                            if cli.quiet == true {
-                                print_summary(&vr);
-                            } else {
-                                if let Err(e) = run_tui(vr) {
-                                    eprintln!("TUI error: {}", e);
-                                    std::process::exit(1);
-                                }
-                            }
-                        }
+                        Ok(vr) => show_result(vr, cli.quiet),
                         Err(e) => {
                             eprintln!("Unknown error: {}", e);
                             std::process::exit(1);
                         }
                     }
                 }
+                "jsonl" | "JSONL" => {
+                    let re = File::open(ifile).and_then(open_input);
+                    match re {
+                        Ok(r) => process_cjseq_stream(
+                            r,
+                            &cli.extensionfiles,
+                            &get_seq_options(&cli),
+                            get_rule_set(&cli),
+                        ),
+                        Err(e) => {
+                            eprintln!("ERROR: cannot read {}: {}", ifile.display(), e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
                 _ => {
-                    eprintln!("ERROR: file extension .{} not supported (only .json)", fext);
+                    eprintln!(
+                        "ERROR: file extension .{} not supported (only .json or .jsonl, possibly compressed with .gz or .zst)",
+                        fext
+                    );
                     std::process::exit(1);
                 }
             }
         }
-        None => {
-            process_cjseq_stream(&cli.extensionfiles);
+        None => match open_input(std::io::stdin()) {
+            Ok(r) => process_cjseq_stream(
+                r,
+                &cli.extensionfiles,
+                &get_seq_options(&cli),
+                get_rule_set(&cli),
+            ),
+            Err(e) => {
+                eprintln!("ERROR: cannot read stdin: {}", e);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+fn get_seq_options(cli: &Cli) -> SeqOptions {
+    if let Some(f) = cli.sample {
+        if !(0.0..=1.0).contains(&f) {
+            eprintln!("ERROR: --sample must be between 0 and 1");
+            std::process::exit(1);
         }
     }
+    SeqOptions {
+        start_line: cli.start_line.unwrap_or(1),
+        max_features: cli.max_features,
+        every: cli.every.unwrap_or(1).max(1),
+        sample: cli.sample,
+        seed: cli.seed,
+        max_errors: cli.max_errors,
+    }
 }
 
-fn validate_cityjson_file(ifile: &PathBuf, extpaths: &Vec<PathBuf>) -> Result<ValidationResult> {
-    let p1 = ifile.canonicalize()?;
-    let s1 = std::fs::read_to_string(&p1)?;
+/// The rules used for the validation: the options, the profile, the
+/// schemas of the attributes and the baseline
+#[derive(Clone)]
+struct RuleSet {
+    options: ValidationOptions,
+    profile: Option<Profile>,
+    attribute_schemas: serde_json::Map<String, serde_json::Value>,
+    baseline: Option<Baseline>,
+    /// Where the baseline of the run is written (--write-baseline)
+    write_baseline: Option<PathBuf>,
+}
 
-    let mut val = CJValidator::from_str(&s1);
+impl RuleSet {
+    fn apply(&self, val: &mut CJValidator) {
+        val.set_options(self.options.clone());
+        if let Some(p) = &self.profile {
+            val.set_profile(p.clone());
+        }
+        for (cotype, schema) in &self.attribute_schemas {
+            //-- they are compiled in get_rule_set(), but the "$ref" depend
+            //-- on the version of the file
+            if let Err(e) = val.add_attribute_schema(cotype, schema) {
+                eprintln!("ERROR: {}", e);
+                std::process::exit(1);
+            }
+        }
+        if let Some(b) = &self.baseline {
+            val.set_baseline(b.clone());
+        }
+    }
+
+    /// eg "delivery-contract (unused_vertices=off) + profile '3D BAG'
+    /// + attribute schemas (Building, Road)"
+    fn describe(&self) -> String {
+        let mut options = self.options.clone();
+        if options.error_limit() == Some(DEFAULT_ERROR_LIMIT) {
+            options.set_error_limit(None);
+        }
+        let mut s = options.to_string();
+        if let Some(p) = &self.profile {
+            s += &format!(" + profile '{}'", p.name());
+        }
+        if !self.attribute_schemas.is_empty() {
+            let cotypes: Vec<&str> = self.attribute_schemas.keys().map(|k| k.as_str()).collect();
+            s += &format!(" + attribute schemas ({})", cotypes.join(", "));
+        }
+        if let Some(b) = &self.baseline {
+            s += &format!(" + baseline ({} entries)", b.len());
+        }
+        s
+    }
+
+    /// Writes the baseline of the run, if asked
+    fn write_baseline(&self, baseline: &Baseline) {
+        if let Some(p) = &self.write_baseline {
+            match std::fs::write(p, baseline.to_json_string()) {
+                Ok(()) => eprintln!(
+                    "Baseline with {} entries written to {}",
+                    baseline.len(),
+                    p.display()
+                ),
+                Err(e) => {
+                    eprintln!("ERROR: cannot write {}: {}", p.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// The rule set: the config file (or "cjval.toml" if it exists), then the
+/// checks given with --error, --warning, --disable, --exhaustive and the
+/// limit of errors, the profile, the schemas of the attributes and the
+/// baseline
+fn get_rule_set(cli: &Cli) -> RuleSet {
+    let exit = |e: String| -> ! {
+        eprintln!("ERROR: {}", e);
+        std::process::exit(1);
+    };
+    let config = match &cli.config {
+        Some(p) => Some(p.clone()),
+        None => Some(PathBuf::from("cjval.toml")).filter(|p| p.exists()),
+    };
+    //-- the library lists all the errors, cjval only the first ones
+    let mut opts = ValidationOptions::new();
+    opts.set_error_limit(Some(DEFAULT_ERROR_LIMIT));
+    if let Some(p) = config {
+        let s = std::fs::read_to_string(&p)
+            .unwrap_or_else(|e| exit(format!("cannot read {}: {}", p.display(), e)));
+        opts.update_from_toml_str(&s)
+            .unwrap_or_else(|e| exit(format!("{}: {}", p.display(), e)));
+    }
+    for (checks, severity) in [
+        (&cli.error, Severity::Error),
+        (&cli.warning, Severity::Warning),
+        (&cli.disable, Severity::Off),
+    ] {
+        for c in checks {
+            opts.set(c, severity).unwrap_or_else(|e| exit(e));
+        }
+    }
+    if cli.exhaustive {
+        opts.set_exhaustive(true);
+    }
+    if let Some(n) = cli.error_limit {
+        opts.set_error_limit(Some(n).filter(|n| *n > 0));
+    }
+    //-- the baseline must have all the errors
+    if cli.all_errors || cli.write_baseline.is_some() {
+        opts.set_error_limit(None);
+    }
+    let profile = cli.profile.as_ref().map(|p| {
+        let s = std::fs::read_to_string(p)
+            .unwrap_or_else(|e| exit(format!("cannot read {}: {}", p.display(), e)));
+        let re = if get_input_extension(p).eq_ignore_ascii_case("toml") {
+            Profile::from_toml_str(&s)
+        } else {
+            Profile::from_json_str(&s)
+        };
+        re.unwrap_or_else(|e| exit(format!("{}: {}", p.display(), e)))
+    });
+    let attribute_schemas = match &cli.attribute_schemas {
+        Some(p) => {
+            let s = std::fs::read_to_string(p)
+                .unwrap_or_else(|e| exit(format!("cannot read {}: {}", p.display(), e)));
+            serde_json::from_str(&s).unwrap_or_else(|e| {
+                exit(format!(
+                    "{}: not a JSON object (CityObject type -> schema): {}",
+                    p.display(),
+                    e
+                ))
+            })
+        }
+        None => serde_json::Map::new(),
+    };
+    //-- a schema that cannot be compiled is rejected before the validation
+    let mut val = CJValidator::from_str("{}");
+    for (cotype, schema) in &attribute_schemas {
+        val.add_attribute_schema(cotype, schema)
+            .unwrap_or_else(|e| exit(e.to_string()));
+    }
+    //-- the library accepts the names of added checks, not the binary
+    let names = val.get_check_names();
+    if let Some(c) = opts.rules().keys().find(|c| !names.contains(c)) {
+        exit(format!(
+            "unknown check '{}' (must be one of: {})",
+            c,
+            names[1..].join(", ")
+        ));
+    }
+    let baseline = cli.baseline.as_ref().map(|p| {
+        let s = std::fs::read_to_string(p)
+            .unwrap_or_else(|e| exit(format!("cannot read {}: {}", p.display(), e)));
+        Baseline::from_json_str(&s).unwrap_or_else(|e| exit(format!("{}: {}", p.display(), e)))
+    });
+    RuleSet {
+        options: opts,
+        profile,
+        attribute_schemas,
+        baseline,
+        write_baseline: cli.write_baseline.clone(),
+    }
+}
+
+/// Returns the extension of the file, without the compression one
+/// (eg "json" for "myfile.city.json.gz")
+fn get_input_extension(ifile: &Path) -> String {
+    let ext = |p: &Path| {
+        p.extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    match ext(ifile).as_str() {
+        "gz" | "GZ" | "zst" | "ZST" | "zstd" => ext(&ifile.with_extension("")),
+        e => e.to_string(),
+    }
+}
+
+/// Wraps the input in a decompressor if it is compressed with gzip or zstd
+/// (detected with the magic bytes), otherwise it is read as is
+fn open_input<R: Read + 'static>(r: R) -> io::Result<Box<dyn BufRead>> {
+    let mut br = BufReader::new(r);
+    let magic = br.fill_buf()?;
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(
+            br,
+        ))))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Ok(Box::new(BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(br)?,
+        )))
+    } else {
+        Ok(Box::new(br))
+    }
+}
+
+fn validate_cityjson_file(
+    ifile: &PathBuf,
+    extpaths: &Vec<PathBuf>,
+    rules: RuleSet,
+) -> Result<ValidationResult> {
+    let p1 = ifile.canonicalize()?;
+    let mut val = CJValidator::from_reader_with_locations(open_input(File::open(&p1)?)?);
+    rules.apply(&mut val);
 
     let schema_version = if val.get_input_cityjson_version() == 0 {
         "none".to_string()
@@ -136,21 +500,35 @@ fn validate_cityjson_file(ifile: &PathBuf, extpaths: &Vec<PathBuf>) -> Result<Va
     }
 
     let valsumm = val.validate();
+    if rules.write_baseline.is_some() {
+        let mut baseline = Baseline::new();
+        baseline.add_results(&valsumm);
+        rules.write_baseline(&baseline);
+    }
 
     let mut errors: Vec<(String, Vec<String>)> = Vec::new();
     let mut warnings: Vec<(String, Vec<String>)> = Vec::new();
 
+    let mut nerrors = ext_errors.len();
+    let mut nwarnings = 0;
     if !ext_errors.is_empty() {
         errors.push(("Extensions".to_string(), ext_errors));
     }
 
     for (criterion, summ) in valsumm.iter() {
         if summ.has_errors() {
-            let err_list: Vec<String> = summ.get_errors().clone();
+            let err_list = error_list(summ);
+            let category = if summ.may_be_cascading() {
+                format!("{} (may be cascading)", criterion)
+            } else {
+                criterion.clone()
+            };
             if summ.is_warning() {
-                warnings.push((criterion.clone(), err_list));
+                nwarnings += summ.get_error_count();
+                warnings.push((category, err_list));
             } else {
-                errors.push((criterion.clone(), err_list));
+                nerrors += summ.get_error_count();
+                errors.push((category, err_list));
             }
         }
     }
@@ -167,12 +545,152 @@ fn validate_cityjson_file(ifile: &PathBuf, extpaths: &Vec<PathBuf>) -> Result<Va
         file_path: p1.to_string_lossy().to_string(),
         schema_version,
         extensions,
+        rules: Some(rules.describe()),
+        ignored: valsumm.values().map(|s| s.get_ignored_count()).sum(),
+        nerrors,
+        nwarnings,
         errors,
         warnings,
         validity,
     })
 }
 
+fn validate_extension_file(ifile: &Path) -> Result<ValidationResult> {
+    let p1 = ifile.canonicalize()?;
+    let s = std::fs::read_to_string(&p1)?;
+    let re = cjval::validate_extension_str(&s);
+    let schema_version = match &re.version_cityjson {
+        Some(v) => format!("Extension for v{}", v),
+        None => "none".to_string(),
+    };
+    let errors: Vec<(String, Vec<String>)> = re.errors_by_check().into_iter().collect();
+    let validity = if errors.is_empty() {
+        Validity::Valid
+    } else {
+        Validity::Invalid
+    };
+    Ok(ValidationResult {
+        file_path: p1.to_string_lossy().to_string(),
+        schema_version,
+        extensions: Vec::new(),
+        rules: None,
+        ignored: 0,
+        nerrors: errors.iter().map(|(_, v)| v.len()).sum(),
+        nwarnings: 0,
+        errors,
+        warnings: Vec::new(),
+        validity,
+    })
+}
+
+fn diff_extension_files(old: &Path, new: &Path) {
+    let read = |p: &Path| match std::fs::read_to_string(p) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("ERROR: cannot read {}: {}", p.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let diff = match cjval::diff_extensions_str(&read(old), &read(new)) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    };
+    for c in &diff.changes {
+        let emoji = if c.breaking { "❌" } else { "✅" };
+        println!("{}\t{}\t{}", emoji, c.path, c.message);
+    }
+    if diff.is_breaking() {
+        println!("❌ The new version of the Extension has breaking changes");
+        std::process::exit(1);
+    } else {
+        println!("✅ The new version of the Extension is compatible");
+    }
+}
+
+/// Shows the result, the exit code is 1 if the file is invalid (the errors
+/// ignored with the baseline don't count)
+fn show_result(vr: ValidationResult, quiet: bool) {
+    let invalid = vr.validity == Validity::Invalid;
+    if quiet {
+        print_summary(&vr);
+    } else if let Err(e) = run_tui(vr) {
+        eprintln!("TUI error: {}", e);
+        std::process::exit(1);
+    }
+    if invalid {
+        std::process::exit(1);
+    }
+}
+
+fn compare_cityjson_cjseq(ifile: &Path, seqfile: &Path) {
+    let re = File::open(ifile).and_then(open_input);
+    let cj = match re {
+        Ok(r) => CJValidator::from_reader(r),
+        Err(e) => {
+            eprintln!("ERROR: cannot read {}: {}", ifile.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let mut eq = match Equivalence::new(&cj) {
+        Ok(eq) => eq,
+        Err(e) => {
+            eprintln!("ERROR: {}: {}", ifile.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let re = if seqfile.as_os_str() == "-" {
+        open_input(std::io::stdin())
+    } else {
+        File::open(seqfile).and_then(open_input)
+    };
+    let reader = match re {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("ERROR: cannot read {}: {}", seqfile.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let mut val: Option<CJValidator> = None;
+    for (i, line) in reader.lines().enumerate() {
+        let l = match line {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("ERROR: cannot read line {}: {}", i + 1, e);
+                std::process::exit(1);
+            }
+        };
+        if l.is_empty() {
+            continue;
+        }
+        match val.as_mut() {
+            None => val = Some(CJValidator::from_str(&l)),
+            Some(v) => {
+                if let Err(e) = v.from_str_cjfeature(&l) {
+                    eprintln!("ERROR: line {}: {}", i + 1, e);
+                    std::process::exit(1);
+                }
+                eq.add_feature(v);
+            }
+        }
+    }
+    let report = eq.finish();
+    if report.is_equivalent() {
+        println!("✅ CityJSON and CityJSONSeq are equivalent");
+    } else {
+        println!("{}", report);
+        println!(
+            "❌ CityJSON and CityJSONSeq are different ({} missing, {} extra, {} different)",
+            report.missing.len(),
+            report.extra.len(),
+            report.different.len()
+        );
+        std::process::exit(1);
+    }
+}
+
 fn run_tui(result: ValidationResult) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -199,10 +717,16 @@ fn run_tui(result: ValidationResult) -> Result<()> {
 
     // Print final summary to stdout after TUI closes
     print_summary(&result);
-    std::process::exit(0);
+    Ok(())
 }
 
 fn print_summary(result: &ValidationResult) {
+    if let Some(r) = result.rules.as_ref().filter(|r| *r != "default") {
+        println!("Rules: {}", r);
+    }
+    if result.ignored > 0 {
+        println!("Ignored (baseline): {}", result.ignored);
+    }
     match result.validity {
         Validity::Valid => {
             println!("✅ File is valid");
@@ -282,8 +806,8 @@ fn render_summary(frame: &mut Frame, area: Rect, result: &ValidationResult) {
         Validity::Invalid => ("❌ INVALID", Color::Red),
     };
 
-    let error_count: usize = result.errors.iter().map(|(_, v)| v.len()).sum();
-    let warning_count: usize = result.warnings.iter().map(|(_, v)| v.len()).sum();
+    let error_count = result.nerrors;
+    let warning_count = result.nwarnings;
 
     let mut summary_text = vec![
         Line::from(vec![
@@ -326,6 +850,16 @@ fn render_summary(frame: &mut Frame, area: Rect, result: &ValidationResult) {
         }
     }
 
+    if let Some(r) = &result.rules {
+        summary_text.push(Line::from(vec![
+            Span::styled(
+                "Rules:      ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(r),
+        ]));
+    }
+
     summary_text.push(Line::from(vec![
         Span::styled(
             "Errors:     ",
@@ -354,6 +888,15 @@ fn render_summary(frame: &mut Frame, area: Rect, result: &ValidationResult) {
             }),
         ),
     ]));
+    if result.ignored > 0 {
+        summary_text.push(Line::from(vec![
+            Span::styled(
+                "Ignored:    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!("{} (baseline)", result.ignored)),
+        ]));
+    }
 
     let summary = Paragraph::new(summary_text)
         .block(
@@ -514,21 +1057,70 @@ fn render_warnings_panel(frame: &mut Frame, area: Rect, result: &ValidationResul
 }
 
 // Stream processing for CityJSONSeq
-fn process_cjseq_stream(extpaths: &Vec<PathBuf>) {
+fn process_cjseq_stream(
+    reader: Box<dyn BufRead>,
+    extpaths: &Vec<PathBuf>,
+    opts: &SeqOptions,
+    rules: RuleSet,
+) {
     let mut b_metadata = false;
     let mut val = CJValidator::from_str("{}");
-    let stdin = std::io::stdin();
     let mut finalresult: i8 = 1;
     let mut linetotal: u64 = 0;
+    let mut nofeatures: usize = 0;
+    let mut novalidated: usize = 0;
+    let mut noinvalid: usize = 0;
+    let mut stopped: Option<String> = None;
+    let mut rng = opts.seed;
+    let mut baseline = Baseline::new();
+    let mut noignored: usize = 0;
 
-    for (i, line) in stdin.lock().lines().enumerate() {
-        let l = line.unwrap();
+    for (i, line) in reader.lines().enumerate() {
+        let l = match line {
+            Ok(l) => l,
+            Err(e) => {
+                println!("{}\t❌\t[]\tERROR: cannot read the line: {}", i + 1, e);
+                finalresult = -1;
+                break;
+            }
+        };
         if l.is_empty() {
             continue;
         }
+        if b_metadata {
+            if let Some(m) = opts.max_features {
+                if novalidated >= m {
+                    stopped = Some(format!("{} validated feature(s)", m));
+                    break;
+                }
+            }
+            if let Some(k) = opts.max_errors {
+                if noinvalid >= k {
+                    stopped = Some(format!("{} invalid feature(s)", k));
+                    break;
+                }
+            }
+        }
         linetotal += 1;
+        if b_metadata {
+            //-- is this feature part of the ones to validate?
+            if i + 1 < opts.start_line {
+                continue;
+            }
+            nofeatures += 1;
+            if (nofeatures - 1) % opts.every != 0 {
+                continue;
+            }
+            if let Some(f) = opts.sample {
+                if next_random(&mut rng) >= f {
+                    continue;
+                }
+            }
+            novalidated += 1;
+        }
         if !b_metadata {
-            val = CJValidator::from_str(&l);
+            val = CJValidator::from_str_with_locations(&l);
+            rules.apply(&mut val);
             if !val.is_cityjson() {
                 println!(
                     "{}\t❌\t[1st-line for metadata]\t{}",
@@ -551,6 +1143,13 @@ fn process_cjseq_stream(extpaths: &Vec<PathBuf>) {
             match re {
                 Ok(_) => {
                     let valsumm = val.validate();
+                    if rules.write_baseline.is_some() {
+                        baseline.add_results(&valsumm);
+                    }
+                    noignored += valsumm
+                        .values()
+                        .map(|s| s.get_ignored_count())
+                        .sum::<usize>();
                     let status = get_status(&valsumm);
                     match status {
                         1 => {
@@ -586,10 +1185,17 @@ fn process_cjseq_stream(extpaths: &Vec<PathBuf>) {
             }
             b_metadata = true;
         } else {
-            let re = val.from_str_cjfeature(&l);
+            let re = val.from_str_cjfeature_at(&l, i + 1);
             match re {
                 Ok(_) => {
                     let valsumm = val.validate();
+                    if rules.write_baseline.is_some() {
+                        baseline.add_results(&valsumm);
+                    }
+                    noignored += valsumm
+                        .values()
+                        .map(|s| s.get_ignored_count())
+                        .sum::<usize>();
                     let status = get_status(&valsumm);
                     match status {
                         1 => {
@@ -608,6 +1214,7 @@ fn process_cjseq_stream(extpaths: &Vec<PathBuf>) {
                         }
                         -1 => {
                             finalresult = -1;
+                            noinvalid += 1;
                             println!(
                                 "{}\t❌\t[{}]\t{}",
                                 i + 1,
@@ -620,6 +1227,7 @@ fn process_cjseq_stream(extpaths: &Vec<PathBuf>) {
                 }
                 Err(e) => {
                     finalresult = -1;
+                    noinvalid += 1;
                     println!(
                         "{}\t❌\t[{}]\t{}",
                         i + 1,
@@ -633,14 +1241,51 @@ fn process_cjseq_stream(extpaths: &Vec<PathBuf>) {
     println!("\n");
     println!("============= SUMMARY =============");
     println!("Total lines: {:?}", linetotal);
+    if rules.describe() != "default" {
+        println!("Rules: {}", rules.describe());
+    }
+    if noignored > 0 {
+        println!("Ignored (baseline): {}", noignored);
+    }
+    //-- only some of the features were validated
+    let sampled = (novalidated as u64) + 1 != linetotal || stopped.is_some();
+    if sampled {
+        println!("Features validated: {:?}", novalidated);
+        if let Some(r) = &stopped {
+            println!(
+                "Stopped after {}, the rest of the stream is not validated",
+                r
+            );
+        }
+    }
     if finalresult == -1 {
         println!("❌ CityJSONSeq has invalid objects");
+    } else if sampled && finalresult == 0 {
+        println!("🟡 The sampled features of the CityJSONSeq are valid but have warnings");
+    } else if sampled {
+        println!("✅ The sampled features of the CityJSONSeq are valid");
     } else if finalresult == 0 {
         println!("🟡 CityJSONSeq is valid but has warnings");
     } else {
         println!("✅ CityJSONSeq is valid");
     }
     println!("===================================");
+    rules.write_baseline(&baseline);
+    if finalresult == -1 {
+        std::process::exit(1);
+    }
+}
+
+/// Random number in [0, 1) with xorshift64*, good enough for sampling
+fn next_random(state: &mut u64) -> f64 {
+    if *state == 0 {
+        *state = 0x9E3779B97F4A7C15;
+    }
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    let r = state.wrapping_mul(0x2545F4914F6CDD1D);
+    (r >> 11) as f64 / (1u64 << 53) as f64
 }
 
 fn fetch_extensions(
@@ -650,7 +1295,7 @@ fn fetch_extensions(
     let mut b_valid = true;
     let mut extdetails: HashMap<String, (String, String)> = HashMap::new();
 
-    if val.get_input_cityjson_version() >= 11 {
+    if val.get_input_cityjson_version() >= 10 {
         if extpaths.len() > 0 {
             // for fext in extpaths {
             // let s = format!("{}", fext.to_str().unwrap());
@@ -688,36 +1333,11 @@ fn fetch_extensions(
                 }
             }
         } else {
-            let re = val.get_extensions_urls();
-            if re.is_some() {
-                let lexts = re.unwrap();
-                // for (name, ext) in &lexts {
-                //     let s = format!("{}", ext);
-                //     extdetails.insert(s, "".to_string());
-                // }
-                for (name, ext) in lexts {
-                    let ext2 = format!("{}", ext);
-                    let o = download_extension(&ext);
-                    match o {
-                        Ok(l) => {
-                            let re = val.add_one_extension_from_str(&l);
-                            match re {
-                                Ok(_) => {
-                                    extdetails.insert(name, (ext2, "".to_string()));
-                                }
-                                Err(error) => {
-                                    b_valid = false;
-                                    extdetails.insert(name, (ext2, format!("{}", error)));
-                                }
-                            }
-                        }
-                        Err(error) => {
-                            // let s: String = format!("{}", error);
-                            extdetails.insert(name, (ext2, format!("{}", error)));
-                            b_valid = false;
-                        }
-                    }
+            for (name, st) in val.resolve_extensions(&HttpResolver::new()) {
+                if !st.is_ok() {
+                    b_valid = false;
                 }
+                extdetails.insert(name, (st.url, st.error.unwrap_or_default()));
             }
         }
     }
@@ -733,12 +1353,30 @@ fn get_errors_string(valsumm: &IndexMap<String, ValSummary>) -> String {
     for (_criterion, summ) in valsumm.iter() {
         if summ.has_errors() == true {
             use std::fmt::Write as fmtwrite;
-            write!(&mut s, "{} | ", summ).expect("Problem writing String");
+            if summ.may_be_cascading() {
+                s.push_str("(may be cascading) ");
+            }
+            write!(&mut s, "{} | ", error_list(summ).join("\n")).expect("Problem writing String");
         }
     }
     s
 }
 
+/// The errors of a check, with their location in the input
+fn error_list(summ: &ValSummary) -> Vec<String> {
+    let ds = summ.get_diagnostics();
+    let mut l: Vec<String> = ds
+        .iter()
+        .map(|d| match d.location {
+            Some(loc) => format!("{} ({})", d, loc),
+            None => d.to_string(),
+        })
+        .collect();
+    //-- the errors that are only counted ("... and 12 more")
+    l.extend(summ.get_errors()[ds.len()..].iter().cloned());
+    l
+}
+
 fn get_status(valsumm: &IndexMap<String, ValSummary>) -> i8 {
     let mut has_errors = false;
     let mut has_warnings = false;
@@ -759,14 +1397,3 @@ fn get_status(valsumm: &IndexMap<String, ValSummary>) -> i8 {
         -1
     }
 }
-
-#[tokio::main]
-async fn download_extension(theurl: &str) -> Result<String> {
-    let u = Url::parse(theurl).unwrap();
-    let res = reqwest::get(u).await?;
-    if res.status().is_success() {
-        Ok(res.text().await?)
-    } else {
-        return Err(anyhow!("Cannot download extension schema: {}", theurl));
-    }
-}
@@ -1,124 +1,84 @@
 extern crate clap;
+use cjval::CJValidator;
 use clap::Parser;
 use std::path::PathBuf;
 
-use jsonschema::{Draft, JSONSchema};
-use serde_json::Value;
-
 #[derive(Parser)]
 #[command(version, about = "Validation of a CityJSON Extension file", long_about = None)]
 struct Cli {
     /// CityJSON Extension file
     inputfile: PathBuf,
+    /// CityJSON file to validate with the Extension (can be repeated)
+    #[arg(long = "sample", value_name = "FILE")]
+    samples: Vec<PathBuf>,
 }
 
-static CITYJSON_FILES: [&str; 4] = [
-    "cityobjects.schema.json",
-    "geomprimitives.schema.json",
-    "appearance.schema.json",
-    "geomtemplates.schema.json",
-];
-
 fn main() {
     let cli = Cli::parse();
-    let mut valid = true;
     if !cli.inputfile.exists() {
         eprintln!(
             "ERROR: Input file {} doesn't exist",
             cli.inputfile.display()
         );
-        std::process::exit(0);
+        std::process::exit(1);
     }
     //-- fetch the instance (the Extension)
     let p1 = cli.inputfile.canonicalize().unwrap();
     let s1 = std::fs::read_to_string(&p1).expect("Couldn't read the file");
-    let re: Result<Value, _> = serde_json::from_str(&s1);
-    if re.is_err() {
-        valid = false;
-        println!("errors: {:?}", re.as_ref().err().unwrap());
+    let re = cjval::validate_extension_str(&s1);
+    for e in &re.errors {
+        println!("ERROR: {}", e);
     }
-    let j: Value = re.unwrap();
-
-    let schema;
-    //-- fetch the correct schema
-    match j["versionCityJSON"].as_str() {
-        Some("1.1") => {
-            let schema_str = include_str!("../../schemas/extensions/11/extension.schema.json");
-            schema = serde_json::from_str(schema_str).unwrap();
-        }
-        Some("2.0") => {
-            let schema_str = include_str!("../../schemas/extensions/20/extension.schema.json");
-            schema = serde_json::from_str(schema_str).unwrap();
-        }
-        _ => {
-            println!("ERROR: the \"versionCityJSON\" property must be \"1.1\" or \"2.0\"");
-            println!("❌");
-            return;
-        }
-    }
-    // let schema = serde_json::from_str(schema_str).unwrap();
-    let compiled = JSONSchema::options()
-        .with_draft(Draft::Draft7)
-        .compile(&schema)
-        .expect("A valid schema");
-    let result = compiled.validate(&j);
-    // let mut ls_errors: Vec<String> = Vec::new();
-    if let Err(errors) = result {
-        valid = false;
-        for error in errors {
-            let s: String = format!("{} [path:{}]", error, error.instance_path);
-            // ls_errors.push(s);
-            println!("ERROR: {}", s);
-        }
-    }
-
-    //-- validate the URLs and $ref, only a few allowed
-    validate_all_ref(&j, &mut valid);
-
-    if valid == true {
+    let mut valid = re.is_valid();
+    if valid {
         println!("✅");
     } else {
         println!("❌");
+        if re.version_cityjson.is_none() {
+            std::process::exit(1);
+        }
     }
-    std::process::exit(0);
+
+    //-- validate the samples with the Extension
+    for sample in &cli.samples {
+        valid &= validate_sample(sample, &s1);
+    }
+    std::process::exit(if valid { 0 } else { 1 });
 }
 
-fn validate_all_ref(j: &Value, valid: &mut bool) {
-    if j.is_object() == true {
-        let jo = j.as_object().unwrap();
-        for p in jo.keys() {
-            let tmp = jo.get(p).unwrap();
-            if tmp.is_object() {
-                validate_all_ref(&tmp, valid);
-            }
-            if tmp.is_array() {
-                let jo = tmp.as_array().unwrap();
-                for each in jo {
-                    validate_all_ref(&each, valid);
-                }
-            }
-            if tmp.is_string() {
-                if p.starts_with("$ref") {
-                    let tmp2 = tmp.as_str().unwrap();
-                    if tmp2.starts_with("#") == false {
-                        let mut b = false;
-                        for each in CITYJSON_FILES {
-                            if tmp2.starts_with(&each) == true {
-                                b = true;
-                            }
-                        }
-                        if b == false {
-                            *valid = false;
-                            println!("ERROR: {:?} not found.", tmp2);
-                        }
-                    }
-                }
-            }
+/// Returns whether the sample is valid (warnings are allowed)
+fn validate_sample(sample: &PathBuf, sext: &str) -> bool {
+    println!("\n=== Sample {} ===", sample.display());
+    let s = match std::fs::read_to_string(sample) {
+        Ok(s) => s,
+        Err(e) => {
+            println!("ERROR: cannot read the file ({})", e);
+            println!("❌");
+            return false;
         }
-    } else if j.is_array() {
-        let jo = j.as_array().unwrap();
-        for each in jo {
-            validate_all_ref(&each, valid);
+    };
+    let mut val = CJValidator::from_str(&s);
+    if let Err(e) = val.add_one_extension_from_str(sext) {
+        println!("ERROR: {}", e);
+        println!("❌");
+        return false;
+    }
+    let valsumm = val.validate();
+    let mut has_errors = false;
+    for (criterion, summ) in valsumm.iter() {
+        for e in summ.get_errors() {
+            if summ.is_warning() {
+                println!("WARNING [{}]: {}", criterion, e);
+            } else {
+                has_errors = true;
+                println!("ERROR [{}]: {}", criterion, e);
+            }
         }
     }
+    if has_errors {
+        println!("❌");
+    } else {
+        println!("✅");
+    }
+    !has_errors
 }
@@ -0,0 +1,487 @@
+//! The checks performed by [`crate::CJValidator::validate`].
+//!
+//! Each check implements the [`Check`] trait, the built-in ones (*schema*,
+//! *wrong_vertex_index*, etc.) and those added with
+//! [`crate::CJValidator::add_check`], eg for project-specific rules. The
+//! results of the added checks are reported with the built-in ones (and
+//! change the validity of the file if they are errors).
+//!
+//! ```rust
+//! use cjval::{CJValidator, Check, Diagnostic, ValidationContext};
+//!
+//! /// Every Building must have a "height" attribute
+//! struct BuildingHeight;
+//!
+//! impl Check for BuildingHeight {
+//!     fn name(&self) -> &str {
+//!         "building_height"
+//!     }
+//!     fn run(&self, ctx: &ValidationContext) -> Vec<Diagnostic> {
+//!         let mut ls = Vec::new();
+//!         for (id, co) in ctx.cityobjects() {
+//!             if co["type"] == "Building" && co["attributes"].get("height").is_none() {
+//!                 ls.push(Diagnostic::new("Building has no height").with_cityobject(id));
+//!             }
+//!         }
+//!         ls
+//!     }
+//! }
+//!
+//! let s1 = std::fs::read_to_string("./data/cube.city.json").unwrap();
+//! let mut v = CJValidator::from_str(&s1);
+//! v.add_check(BuildingHeight).unwrap();
+//! let re = v.validate();
+//! for e in re["building_height"].get_errors() {
+//!     println!("{}", e);
+//! }
+//! ```
+
+use crate::baseline::BaselineIndex;
+use crate::options::default_severity;
+use crate::profile;
+use crate::{CJValidator, ExtensionRef, Location, Severity, ValidationOptions};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A validation check
+pub trait Check: Send + Sync {
+    /// The name of the check, it is the key in the results of
+    /// [`crate::CJValidator::validate`] (eg "building_height")
+    fn name(&self) -> &str;
+
+    /// The default severity of the check (an error by default), a warning
+    /// doesn't make the file invalid. The checks that are warnings are
+    /// performed after the errors.
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// Returns false if the check is not performed for this file (eg because
+    /// what it checks is not given)
+    fn applies(&self, _ctx: &ValidationContext) -> bool {
+        true
+    }
+
+    /// Performs the check, no diagnostics means that it is valid
+    fn run(&self, ctx: &ValidationContext) -> Vec<Diagnostic>;
+}
+
+impl fmt::Debug for dyn Check {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Check({})", self.name())
+    }
+}
+
+/// An error (or a warning) found by a [`Check`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// The number of identical errors it stands for (they are grouped)
+    pub count: usize,
+    /// The id of the CityObject concerned, if there is one
+    pub cityobject: Option<String>,
+    /// The JSON Pointer of the value concerned, if known (eg
+    /// "/CityObjects/id-1/geometry/0")
+    pub pointer: Option<String>,
+    /// The location in the input of the value concerned (found with the
+    /// JSON Pointer, or else with the CityObject), only if the validator
+    /// keeps them (eg [`crate::CJValidator::from_str_with_locations`])
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    pub fn new(message: &str) -> Self {
+        Diagnostic {
+            message: message.to_string(),
+            count: 1,
+            cityobject: None,
+            pointer: None,
+            location: None,
+        }
+    }
+
+    pub fn with_cityobject(mut self, id: &str) -> Self {
+        self.cityobject = Some(id.to_string());
+        self
+    }
+
+    pub fn with_pointer(mut self, pointer: &str) -> Self {
+        self.pointer = Some(pointer.to_string());
+        self
+    }
+
+    /// The diagnostic of the value at a JSON Pointer, its CityObject is the
+    /// one of the pointer (if there is one)
+    pub(crate) fn at(pointer: &str, message: &str) -> Self {
+        let mut d = Diagnostic::new(message).with_pointer(pointer);
+        if let Some(rest) = pointer.strip_prefix("/CityObjects/") {
+            let id = rest.split('/').next().unwrap_or(rest);
+            d.cityobject = Some(id.replace("~1", "/").replace("~0", "~"));
+        }
+        d
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// The message, with the CityObject id if it is not already in it, and
+    /// the number of times it occurs
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match &self.cityobject {
+            Some(id) if !self.message.contains(id.as_str()) => {
+                write!(fmt, "CityObject #{}: {}", id, self.message)?
+            }
+            _ => fmt.write_str(&self.message)?,
+        }
+        if self.count > 1 {
+            write!(fmt, " ({} times)", thousands(self.count))?;
+        }
+        Ok(())
+    }
+}
+
+/// eg "1,203,442"
+pub(crate) fn thousands(n: usize) -> String {
+    let s = n.to_string();
+    let mut re = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if i > 0 && (s.len() - i) % 3 == 0 {
+            re.push(',');
+        }
+        re.push(c);
+    }
+    re
+}
+
+/// The errors of a check: the identical ones are grouped, after `limit`
+/// different ones they are only counted (to not exhaust the memory with
+/// millions of errors), and those in the baseline are ignored
+pub(crate) struct ErrorList<'a> {
+    check: &'a str,
+    limit: Option<usize>,
+    baseline: Option<&'a BaselineIndex<'a>>,
+    diagnostics: Vec<Diagnostic>,
+    groups: HashMap<(String, Option<String>, Option<String>), usize>,
+    more: usize,
+    ignored: Vec<Diagnostic>,
+    nignored: usize,
+}
+
+impl<'a> ErrorList<'a> {
+    pub(crate) fn new(
+        check: &'a str,
+        limit: Option<usize>,
+        baseline: Option<&'a BaselineIndex<'a>>,
+    ) -> Self {
+        ErrorList {
+            check,
+            limit,
+            baseline,
+            diagnostics: Vec::new(),
+            groups: HashMap::new(),
+            more: 0,
+            ignored: Vec::new(),
+            nignored: 0,
+        }
+    }
+
+    /// Adds an error that concerns no CityObject (eg a root property)
+    pub(crate) fn push(&mut self, message: String) {
+        self.push_diagnostic(Diagnostic::new(&message));
+    }
+
+    /// Adds an error of a CityObject
+    pub(crate) fn push_in(&mut self, id: &str, message: String) {
+        self.push_diagnostic(Diagnostic::new(&message).with_cityobject(id));
+    }
+
+    /// Adds an error of the value at a JSON Pointer (its CityObject is the
+    /// one of the pointer, if there is one)
+    pub(crate) fn push_at(&mut self, pointer: &str, message: String) {
+        self.push_diagnostic(Diagnostic::at(pointer, &message));
+    }
+
+    pub(crate) fn push_diagnostic(&mut self, d: Diagnostic) {
+        let full = |l: &Vec<Diagnostic>| self.limit.is_some_and(|m| l.len() >= m);
+        if self.baseline.is_some_and(|b| b.is_ignored(self.check, &d)) {
+            self.nignored += d.count;
+            if !full(&self.ignored) {
+                self.ignored.push(d);
+            }
+            return;
+        }
+        let key = (d.message.clone(), d.cityobject.clone(), d.pointer.clone());
+        if let Some(&i) = self.groups.get(&key) {
+            self.diagnostics[i].count += d.count;
+        } else if full(&self.diagnostics) {
+            self.more += d.count;
+        } else {
+            self.groups.insert(key, self.diagnostics.len());
+            self.diagnostics.push(d);
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty() && self.more == 0
+    }
+
+    /// The diagnostics, the number of errors not listed, the ignored
+    /// diagnostics and the number of errors ignored
+    pub(crate) fn into_parts(self) -> (Vec<Diagnostic>, usize, Vec<Diagnostic>, usize) {
+        (self.diagnostics, self.more, self.ignored, self.nignored)
+    }
+}
+
+/// What a [`Check`] can read: the CityJSON object (or the CityJSONFeature),
+/// the Extensions and the options of the validation
+pub struct ValidationContext<'a> {
+    v: &'a CJValidator,
+}
+
+impl<'a> ValidationContext<'a> {
+    pub(crate) fn new(v: &'a CJValidator) -> Self {
+        ValidationContext { v }
+    }
+
+    /// The CityJSON object, or the CityJSONFeature for a CityJSONSeq
+    pub fn cityjson(&self) -> &'a Value {
+        &self.v.j
+    }
+
+    /// For a CityJSONSeq, the 1st line (the CityJSON object with the
+    /// "transform", "metadata", etc.); None otherwise
+    pub fn header(&self) -> Option<&'a Value> {
+        Some(&self.v.jheader).filter(|_| self.v.is_cjfeature)
+    }
+
+    /// Returns true if a CityJSONFeature is validated
+    pub fn is_cjfeature(&self) -> bool {
+        self.v.is_cjfeature
+    }
+
+    /// The CityJSON version of the file (eg "2.0")
+    pub fn version(&self) -> &'a str {
+        self.header().unwrap_or(&self.v.j)["version"]
+            .as_str()
+            .unwrap_or("")
+    }
+
+    /// A root property of the CityJSONFeature, or if it's not there the one
+    /// of the 1st line of the CityJSONSeq (eg "transform" or "appearance")
+    pub fn root_property(&self, property: &str) -> &'a Value {
+        match self.v.j.get(property) {
+            Some(x) => x,
+            None => &self.v.jheader[property],
+        }
+    }
+
+    /// Returns (scale, translate) of the "transform", or the identity if
+    /// there is none
+    pub fn transform(&self) -> ([f64; 3], [f64; 3]) {
+        let mut scale = [1.0, 1.0, 1.0];
+        let mut translate = [0.0, 0.0, 0.0];
+        let t = self.root_property("transform");
+        for i in 0..3 {
+            if let Some(x) = t["scale"][i].as_f64() {
+                scale[i] = x;
+            }
+            if let Some(x) = t["translate"][i].as_f64() {
+                translate[i] = x;
+            }
+        }
+        (scale, translate)
+    }
+
+    /// For a CityJSONSeq, the line of the CityJSONFeature (if it was given
+    /// with [`crate::CJValidator::from_str_cjfeature_at`])
+    pub fn feature_line(&self) -> Option<usize> {
+        self.v.feature_line.filter(|_| self.v.is_cjfeature)
+    }
+
+    /// "[feature:id, line:12]" for the current CityJSONFeature
+    pub(crate) fn feature_location(&self) -> String {
+        let id = match &self.v.j["id"] {
+            Value::String(x) => x.to_string(),
+            x => x.to_string(),
+        };
+        match self.feature_line() {
+            Some(l) => format!("[feature:{}, line:{}]", id, l),
+            None => format!("[feature:{}]", id),
+        }
+    }
+
+    /// The Extensions declared in "extensions" (of the 1st line for a
+    /// CityJSONSeq), they are not necessarily added to the validator
+    pub fn declared_extensions(&self) -> Vec<ExtensionRef> {
+        let mut re: Vec<ExtensionRef> = Vec::new();
+        let exts = self.header().unwrap_or(&self.v.j)["extensions"].as_object();
+        for (name, e) in exts.into_iter().flatten() {
+            re.push(ExtensionRef {
+                name: name.to_string(),
+                url: e["url"].as_str().unwrap_or("").to_string(),
+                version: e["version"].as_str().unwrap_or("").to_string(),
+            });
+        }
+        re
+    }
+
+    /// The CityObjects (id, CityObject), empty if there are none
+    pub fn cityobjects(&self) -> impl Iterator<Item = (&'a String, &'a Value)> {
+        self.v.j["CityObjects"].as_object().into_iter().flatten()
+    }
+
+    /// The Extensions added to the validator (their JSON content)
+    pub fn extensions(&self) -> &'a [Value] {
+        &self.v.jexts
+    }
+
+    /// The options of the validation
+    pub fn options(&self) -> &'a ValidationOptions {
+        &self.v.options
+    }
+}
+
+/// A built-in check, it reads the file with the [`ValidationContext`] (only
+/// those using the compiled schemas and the profile read the validator) and
+/// adds its errors to an [`ErrorList`] (some can find millions of errors)
+pub(crate) struct Builtin {
+    name: &'static str,
+    run: fn(&ValidationContext, &mut ErrorList),
+    applies: fn(&ValidationContext) -> bool,
+}
+
+impl Builtin {
+    fn collect(&self, ctx: &ValidationContext, errors: &mut ErrorList) {
+        (self.run)(ctx, errors)
+    }
+}
+
+impl Check for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn severity(&self) -> Severity {
+        default_severity(self.name)
+    }
+
+    fn applies(&self, ctx: &ValidationContext) -> bool {
+        (self.applies)(ctx)
+    }
+
+    fn run(&self, ctx: &ValidationContext) -> Vec<Diagnostic> {
+        let mut errors = ErrorList::new(self.name, None, None);
+        self.collect(ctx, &mut errors);
+        errors.into_parts().0
+    }
+}
+
+/// A built-in check or an added one
+#[derive(Clone, Copy)]
+pub(crate) enum CheckRef<'a> {
+    Builtin(&'static Builtin),
+    Added(&'a dyn Check),
+}
+
+impl CheckRef<'_> {
+    pub(crate) fn check(&self) -> &dyn Check {
+        match self {
+            CheckRef::Builtin(b) => *b,
+            CheckRef::Added(c) => *c,
+        }
+    }
+
+    /// Performs the check, its errors are added to `errors`
+    pub(crate) fn collect(&self, ctx: &ValidationContext, errors: &mut ErrorList) {
+        match self {
+            CheckRef::Builtin(b) => b.collect(ctx, errors),
+            CheckRef::Added(c) => {
+                for d in c.run(ctx) {
+                    errors.push_diagnostic(d);
+                }
+            }
+        }
+    }
+}
+
+/// The validation stops after errors in these checks (unless exhaustive)
+pub(crate) static STOPPING_CHECKS: [&str; 2] = ["schema", "extensions"];
+
+/// The built-in checks, in the order they are performed (*json_syntax* is
+/// not one, the parsing is done when the validator is created)
+static BUILTIN_CHECKS: [Builtin; 13] = [
+    Builtin {
+        name: "schema",
+        run: |ctx, errors| ctx.v.schema(errors),
+        applies: |_| true,
+    },
+    Builtin {
+        name: "extensions",
+        run: |ctx, errors| ctx.v.validate_extensions(errors),
+        applies: |_| true,
+    },
+    Builtin {
+        name: "parents_children_consistency",
+        run: CJValidator::parents_children_consistency,
+        applies: |_| true,
+    },
+    Builtin {
+        name: "wrong_vertex_index",
+        run: CJValidator::wrong_vertex_index,
+        applies: |_| true,
+    },
+    Builtin {
+        name: "semantics_arrays",
+        run: CJValidator::semantics_arrays,
+        applies: |_| true,
+    },
+    Builtin {
+        name: "textures",
+        run: CJValidator::textures,
+        applies: |_| true,
+    },
+    Builtin {
+        name: "materials",
+        run: CJValidator::materials,
+        applies: |_| true,
+    },
+    Builtin {
+        name: "attribute_schemas",
+        run: |ctx, errors| ctx.v.attribute_schemas(errors),
+        applies: |ctx| !ctx.v.attribute_schemas.is_empty(),
+    },
+    Builtin {
+        name: "profile",
+        run: |ctx, errors| {
+            if let Some(p) = &ctx.v.profile {
+                let v = ctx.v;
+                profile::validate_profile(p, &v.j, v.is_cjfeature, &v.profile_seen, errors);
+            }
+        },
+        applies: |ctx| ctx.v.profile.is_some(),
+    },
+    Builtin {
+        name: "extra_root_properties",
+        run: CJValidator::extra_root_properties,
+        applies: |_| true,
+    },
+    Builtin {
+        name: "duplicate_vertices",
+        run: CJValidator::duplicate_vertices,
+        applies: |_| true,
+    },
+    Builtin {
+        name: "unused_vertices",
+        run: CJValidator::unused_vertices,
+        applies: |_| true,
+    },
+    Builtin {
+        name: "extension_versions",
+        run: CJValidator::extension_versions,
+        applies: |_| true,
+    },
+];
+
+pub(crate) fn builtin_checks() -> impl Iterator<Item = &'static Builtin> {
+    BUILTIN_CHECKS.iter()
+}
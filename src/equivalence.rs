@@ -0,0 +1,330 @@
+//! Check whether a CityJSON and a CityJSONSeq describe the same CityObjects,
+//! eg to verify that nothing was lost when converting with
+//! [cjseq](https://github.com/cityjson/cjseq).
+//!
+//! The geometries (and the locations of the addresses) are compared after
+//! their vertices have been dereferenced (and the "transform" applied), the materials and textures are replaced by
+//! the objects they reference in the "appearance", and the GeometryInstance
+//! by their template. The coordinates can differ by the precision of the
+//! "transform".
+
+use crate::CJValidator;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Differences between a CityJSON and a CityJSONSeq
+#[derive(Debug, Default)]
+pub struct EquivalenceReport {
+    /// CityObjects in the CityJSON that are not in the CityJSONSeq
+    pub missing: Vec<String>,
+    /// CityObjects in the CityJSONSeq that are not in the CityJSON
+    /// (or that are in more than one CityJSONFeature)
+    pub extra: Vec<String>,
+    /// CityObjects that are in both but differ: (id, first difference)
+    pub different: Vec<(String, String)>,
+}
+
+impl EquivalenceReport {
+    /// Returns true if the CityJSON and the CityJSONSeq are equivalent
+    pub fn is_equivalent(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.different.is_empty()
+    }
+}
+
+impl fmt::Display for EquivalenceReport {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_equivalent() {
+            return fmt.write_str("ok");
+        }
+        let mut ls: Vec<String> = Vec::new();
+        for id in &self.missing {
+            ls.push(format!("CityObject #{} is missing in the CityJSONSeq", id));
+        }
+        for id in &self.extra {
+            ls.push(format!("CityObject #{} is not in the CityJSON", id));
+        }
+        for (id, d) in &self.different {
+            ls.push(format!("CityObject #{} is different: {}", id, d));
+        }
+        fmt.write_str(&ls.join("\n"))
+    }
+}
+
+/// Compares a CityJSON with the CityJSONFeatures of a CityJSONSeq, one at a time.
+/// ```rust
+/// use cjval::{CJValidator, Equivalence};
+/// let s1 = std::fs::read_to_string("./data/cube.city.json").unwrap();
+/// let cj = CJValidator::from_str(&s1);
+/// let mut eq = Equivalence::new(&cj).unwrap();
+/// let s2 = std::fs::read_to_string("./data/cube_20.jsonl").unwrap();
+/// let mut lines = s2.lines();
+/// let mut seq = CJValidator::from_str(lines.next().unwrap());
+/// for l in lines {
+///     if seq.from_str_cjfeature(l).is_ok() {
+///         eq.add_feature(&seq);
+///     }
+/// }
+/// let report = eq.finish();
+/// println!("{}", report);
+/// ```
+pub struct Equivalence {
+    cos: HashMap<String, Value>,
+    scale: [f64; 3],
+    seen: HashSet<String>,
+    report: EquivalenceReport,
+}
+
+impl Equivalence {
+    /// Prepares the comparison, the CityObjects of the CityJSON are dereferenced
+    pub fn new(cj: &CJValidator) -> Result<Self, String> {
+        if !cj.is_cityjson() || cj.is_cityjsonfeature() {
+            return Err("Not a CityJSON object".to_string());
+        }
+        let (scale, _) = cj.get_transform();
+        let mut cos: HashMap<String, Value> = HashMap::new();
+        if let Some(x) = cj.j["CityObjects"].as_object() {
+            for (id, co) in x {
+                cos.insert(id.to_string(), dereference_cityobject(cj, co));
+            }
+        }
+        Ok(Equivalence {
+            cos,
+            scale,
+            seen: HashSet::new(),
+            report: EquivalenceReport::default(),
+        })
+    }
+
+    /// Compares the CityObjects of one CityJSONFeature (the validator must
+    /// have been loaded with [`CJValidator::from_str_cjfeature`])
+    pub fn add_feature(&mut self, f: &CJValidator) {
+        let (scale, _) = f.get_transform();
+        let tol: f64 = scale
+            .iter()
+            .chain(self.scale.iter())
+            .fold(0.0, |a, b| a.max(*b));
+        let Some(x) = f.j["CityObjects"].as_object() else {
+            return;
+        };
+        for (id, co) in x {
+            if !self.seen.insert(id.to_string()) {
+                self.report.extra.push(id.to_string());
+                continue;
+            }
+            match self.cos.get(id) {
+                Some(co_cj) => {
+                    let co_seq = dereference_cityobject(f, co);
+                    if let Some(d) = diff_values(co_cj, &co_seq, "", tol) {
+                        self.report.different.push((id.to_string(), d));
+                    }
+                }
+                None => self.report.extra.push(id.to_string()),
+            }
+        }
+    }
+
+    /// Returns the report, the CityObjects not seen in any CityJSONFeature
+    /// are reported as missing
+    pub fn finish(mut self) -> EquivalenceReport {
+        let mut missing: Vec<String> = self
+            .cos
+            .keys()
+            .filter(|id| !self.seen.contains(*id))
+            .cloned()
+            .collect();
+        missing.sort();
+        self.report.missing = missing;
+        self.report
+    }
+}
+
+/// Returns a copy of the CityObject where the vertices, materials, textures
+/// and templates referenced by the geometries (and the vertices of the
+/// locations of the addresses) are replaced by their values
+fn dereference_cityobject(v: &CJValidator, co: &Value) -> Value {
+    let mut co = co.clone();
+    let (scale, translate) = v.get_transform();
+    let vertices = v.j["vertices"].as_array();
+    let real = |i: &Value| -> Value {
+        let p = i
+            .as_u64()
+            .and_then(|i| vertices.and_then(|vs| vs.get(i as usize)));
+        match p {
+            Some(p) => {
+                let mut c: Vec<f64> = Vec::new();
+                for i in 0..3 {
+                    c.push(p[i].as_f64().unwrap_or(f64::NAN) * scale[i] + translate[i]);
+                }
+                json!(c)
+            }
+            None => i.clone(),
+        }
+    };
+    let appearance = v.get_with_header("appearance");
+    let templates = v.get_with_header("geometry-templates");
+    if let Some(gs) = co.get_mut("geometry").and_then(|g| g.as_array_mut()) {
+        for g in gs {
+            if let Some(b) = g.get_mut("boundaries") {
+                map_leaves(b, &real);
+            }
+            if let Some(m) = g.get_mut("material").and_then(|m| m.as_object_mut()) {
+                for theme in m.values_mut() {
+                    let mat = |i: &Value| match i.as_u64() {
+                        Some(i) => appearance["materials"][i as usize].clone(),
+                        None => i.clone(),
+                    };
+                    if let Some(x) = theme.get_mut("values") {
+                        map_leaves(x, &mat);
+                    }
+                    if let Some(x) = theme.get_mut("value") {
+                        *x = mat(x);
+                    }
+                }
+            }
+            if let Some(t) = g.get_mut("texture").and_then(|t| t.as_object_mut()) {
+                for theme in t.values_mut() {
+                    if let Some(x) = theme.get_mut("values") {
+                        dereference_texture_values(x, appearance);
+                    }
+                }
+            }
+            if g["type"] == "GeometryInstance" {
+                if let Some(i) = g["template"].as_u64() {
+                    let mut t = templates["templates"][i as usize].clone();
+                    let vt = |i: &Value| match i.as_u64() {
+                        Some(i) => templates["vertices-templates"][i as usize].clone(),
+                        None => i.clone(),
+                    };
+                    if let Some(b) = t.get_mut("boundaries") {
+                        map_leaves(b, &vt);
+                    }
+                    g["template"] = t;
+                }
+            }
+        }
+    }
+    if let Some(ads) = co.get_mut("address").and_then(|a| a.as_array_mut()) {
+        for ad in ads {
+            if let Some(b) = ad.get_mut("location").and_then(|l| l.get_mut("boundaries")) {
+                map_leaves(b, &real);
+            }
+        }
+    }
+    co
+}
+
+/// Applies f to all the non-array values of nested arrays
+fn map_leaves(v: &mut Value, f: &dyn Fn(&Value) -> Value) {
+    match v.as_array_mut() {
+        Some(a) => {
+            for each in a {
+                map_leaves(each, f);
+            }
+        }
+        None => *v = f(v),
+    }
+}
+
+/// The texture "values" are nested arrays where the deepest ones are
+/// [texture index, uv index, uv index, ...] (or [null])
+fn dereference_texture_values(v: &mut Value, appearance: &Value) {
+    let Some(a) = v.as_array_mut() else {
+        return;
+    };
+    if a.iter().any(|x| x.is_array()) {
+        for each in a {
+            dereference_texture_values(each, appearance);
+        }
+        return;
+    }
+    for (i, each) in a.iter_mut().enumerate() {
+        if let Some(n) = each.as_u64() {
+            *each = if i == 0 {
+                appearance["textures"][n as usize].clone()
+            } else {
+                appearance["vertices-texture"][n as usize].clone()
+            };
+        }
+    }
+}
+
+/// Returns the first difference between a and b (None if they are equal),
+/// numbers can differ by tol
+fn diff_values(a: &Value, b: &Value, path: &str, tol: f64) -> Option<String> {
+    match (a, b) {
+        (Value::Object(oa), Value::Object(ob)) => diff_objects(oa, ob, path, tol),
+        (Value::Array(aa), Value::Array(ab)) => {
+            if aa.len() != ab.len() {
+                return Some(format!(
+                    "{}: {} elements != {} elements",
+                    display_path(path),
+                    aa.len(),
+                    ab.len()
+                ));
+            }
+            for (i, (x, y)) in aa.iter().zip(ab.iter()).enumerate() {
+                let d = diff_values(x, y, &format!("{}/{}", path, i), tol);
+                if d.is_some() {
+                    return d;
+                }
+            }
+            None
+        }
+        (Value::Number(na), Value::Number(nb)) => {
+            //-- only the coordinates can differ by the precision of the transform
+            let t = if path.contains("/boundaries") {
+                tol
+            } else {
+                0.0
+            };
+            let (x, y) = (na.as_f64().unwrap(), nb.as_f64().unwrap());
+            if (x - y).abs() <= t + f64::EPSILON * x.abs().max(y.abs()) {
+                None
+            } else {
+                Some(format!("{}: {} != {}", display_path(path), a, b))
+            }
+        }
+        _ => {
+            if a == b {
+                None
+            } else {
+                Some(format!("{}: {} != {}", display_path(path), a, b))
+            }
+        }
+    }
+}
+
+fn diff_objects(
+    a: &Map<String, Value>,
+    b: &Map<String, Value>,
+    path: &str,
+    tol: f64,
+) -> Option<String> {
+    for (k, va) in a {
+        match b.get(k) {
+            Some(vb) => {
+                let d = diff_values(va, vb, &format!("{}/{}", path, k), tol);
+                if d.is_some() {
+                    return d;
+                }
+            }
+            None => return Some(format!("{}/{} is missing", path, k)),
+        }
+    }
+    for k in b.keys() {
+        if !a.contains_key(k) {
+            return Some(format!("{}/{} is extra", path, k));
+        }
+    }
+    None
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
@@ -0,0 +1,292 @@
+//! Compatibility between two versions of an Extension: are the files valid
+//! with the old version still valid with the new one?
+//!
+//! The schemas are compared structurally: what is added is compatible, what
+//! is removed or tightened (new "required" properties, removed "enum" values,
+//! stricter bounds, properties added to an object that allowed any
+//! property, ...) is breaking. The changes that cannot be classified are
+//! considered breaking.
+
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// One change between two versions of an Extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionChange {
+    /// JSON path in the Extension file, eg "/extraCityObjects/+NoiseBuilding/required"
+    pub path: String,
+    pub message: String,
+    /// True if a file valid with the old version might be invalid with the new one
+    pub breaking: bool,
+}
+
+impl fmt::Display for ExtensionChange {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let c = if self.breaking {
+            "breaking"
+        } else {
+            "compatible"
+        };
+        write!(fmt, "[{}] {}: {}", c, self.path, self.message)
+    }
+}
+
+/// The changes between two versions of an Extension, see [`diff_extensions_str`]
+#[derive(Debug, Default)]
+pub struct ExtensionDiff {
+    pub changes: Vec<ExtensionChange>,
+}
+
+impl ExtensionDiff {
+    /// True if at least one change is breaking
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|c| c.breaking)
+    }
+
+    fn push(&mut self, path: &str, message: String, breaking: bool) {
+        self.changes.push(ExtensionChange {
+            path: path.to_string(),
+            message,
+            breaking,
+        });
+    }
+}
+
+impl fmt::Display for ExtensionDiff {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let ls: Vec<String> = self.changes.iter().map(|c| c.to_string()).collect();
+        fmt.write_str(&ls.join("\n"))
+    }
+}
+
+/// Keywords that do not change what is valid
+static ANNOTATIONS: [&str; 5] = ["description", "title", "$comment", "examples", "default"];
+
+/// Compares two versions (the content of the `.ext.json` files) of an Extension.
+/// ```rust
+/// let s = std::fs::read_to_string("./schemas/extensions/20/noise.ext.json").unwrap();
+/// let re = cjval::diff_extensions_str(&s, &s).unwrap();
+/// assert!(re.changes.is_empty());
+/// ```
+pub fn diff_extensions_str(old: &str, new: &str) -> Result<ExtensionDiff, String> {
+    let jold: Value = serde_json::from_str(old).map_err(|e| format!("old Extension: {}", e))?;
+    let jnew: Value = serde_json::from_str(new).map_err(|e| format!("new Extension: {}", e))?;
+    let mut diff = ExtensionDiff::default();
+    if jold["versionCityJSON"] != jnew["versionCityJSON"] {
+        diff.push(
+            "/versionCityJSON",
+            format!("{} -> {}", jold["versionCityJSON"], jnew["versionCityJSON"]),
+            true,
+        );
+    }
+    for p in [
+        "extraCityObjects",
+        "extraRootProperties",
+        "extraSemanticSurfaces",
+        "definitions",
+    ] {
+        diff_definitions(&jold[p], &jnew[p], &format!("/{}", p), &mut diff);
+    }
+    //-- extraAttributes: per CityObject type
+    let empty = Map::new();
+    let aold = jold["extraAttributes"].as_object().unwrap_or(&empty);
+    let anew = jnew["extraAttributes"].as_object().unwrap_or(&empty);
+    for cotype in aold
+        .keys()
+        .chain(anew.keys().filter(|k| !aold.contains_key(*k)))
+    {
+        diff_definitions(
+            &jold["extraAttributes"][cotype],
+            &jnew["extraAttributes"][cotype],
+            &format!("/extraAttributes/{}", cotype),
+            &mut diff,
+        );
+    }
+    Ok(diff)
+}
+
+/// Compares 2 objects whose members are schemas (eg "extraCityObjects")
+fn diff_definitions(old: &Value, new: &Value, path: &str, diff: &mut ExtensionDiff) {
+    let empty = Map::new();
+    let mold = old.as_object().unwrap_or(&empty);
+    let mnew = new.as_object().unwrap_or(&empty);
+    for (k, s) in mold {
+        let p = format!("{}/{}", path, k);
+        match mnew.get(k) {
+            Some(s2) => diff_schemas(s, s2, &p, diff),
+            None => diff.push(&p, "removed".to_string(), true),
+        }
+    }
+    for k in mnew.keys() {
+        if !mold.contains_key(k) {
+            diff.push(&format!("{}/{}", path, k), "added".to_string(), false);
+        }
+    }
+}
+
+fn diff_schemas(old: &Value, new: &Value, path: &str, diff: &mut ExtensionDiff) {
+    if old == new {
+        return;
+    }
+    let (Some(mold), Some(mnew)) = (old.as_object(), new.as_object()) else {
+        diff.push(path, format!("{} -> {}", old, new), true);
+        return;
+    };
+    let keys: Vec<&String> = mold
+        .keys()
+        .chain(mnew.keys().filter(|k| !mold.contains_key(*k)))
+        .collect();
+    for k in keys {
+        let p = format!("{}/{}", path, k);
+        let (o, n) = (mold.get(k), mnew.get(k));
+        if o == n || ANNOTATIONS.contains(&k.as_str()) {
+            continue;
+        }
+        match k.as_str() {
+            "required" => {
+                let (o, n) = (as_strings(o), as_strings(n));
+                for x in n.iter().filter(|x| !o.contains(x)) {
+                    diff.push(&p, format!("'{}' is now required", x), true);
+                }
+                for x in o.iter().filter(|x| !n.contains(x)) {
+                    diff.push(&p, format!("'{}' is not required anymore", x), false);
+                }
+            }
+            "enum" => {
+                let empty: Vec<Value> = Vec::new();
+                let eo = o.and_then(|x| x.as_array()).unwrap_or(&empty);
+                let en = n.and_then(|x| x.as_array()).unwrap_or(&empty);
+                if n.is_none() {
+                    diff.push(&p, "removed".to_string(), false);
+                    continue;
+                }
+                for x in eo.iter().filter(|x| !en.contains(x)) {
+                    diff.push(&p, format!("{} removed", x), true);
+                }
+                for x in en.iter().filter(|x| !eo.contains(x)) {
+                    diff.push(&p, format!("{} added", x), o.is_none());
+                }
+            }
+            "type" => {
+                let (o, n) = (as_strings(o), as_strings(n));
+                //-- no "type" means all types
+                let removed = !n.is_empty() && (o.is_empty() || o.iter().any(|x| !n.contains(x)));
+                diff.push(&p, format!("{:?} -> {:?}", o, n), removed);
+            }
+            "additionalProperties" => {
+                //-- absent or true means everything is allowed
+                let allows_all = |x: Option<&Value>| x.is_none() || x == Some(&Value::Bool(true));
+                if allows_all(n) {
+                    diff.push(&p, "all additional properties allowed".to_string(), false);
+                } else if allows_all(o) {
+                    diff.push(&p, "additional properties restricted".to_string(), true);
+                } else {
+                    diff_schemas(o.unwrap(), n.unwrap(), &p, diff);
+                }
+            }
+            "minimum" | "exclusiveMinimum" | "minLength" | "minItems" | "minProperties" => {
+                diff_bound(o, n, &p, true, diff)
+            }
+            "maximum" | "exclusiveMaximum" | "maxLength" | "maxItems" | "maxProperties" => {
+                diff_bound(o, n, &p, false, diff)
+            }
+            "properties" | "patternProperties" | "definitions" => {
+                //-- a removed definition breaks its "$ref", and a definition
+                //-- added is used only if referenced
+                let closed = k == "definitions"
+                    || mnew.get("additionalProperties") == Some(&Value::Bool(false));
+                match (o, n) {
+                    (Some(o), Some(n)) => diff_properties(o, n, &p, closed, diff),
+                    (None, Some(_)) => {
+                        diff.push(&p, "added".to_string(), k == "patternProperties" || !closed)
+                    }
+                    (Some(_), None) => diff.push(&p, "removed".to_string(), k == "definitions"),
+                    (None, None) => (),
+                }
+            }
+            "items" | "not" | "if" | "then" | "else" => match (o, n) {
+                (Some(o), Some(n)) => diff_schemas(o, n, &p, diff),
+                (None, Some(_)) => diff.push(&p, "added".to_string(), true),
+                (Some(_), None) => diff.push(&p, "removed".to_string(), false),
+                (None, None) => (),
+            },
+            "allOf" | "anyOf" | "oneOf" => {
+                let empty: Vec<Value> = Vec::new();
+                let ao = o.and_then(|x| x.as_array()).unwrap_or(&empty);
+                let an = n.and_then(|x| x.as_array()).unwrap_or(&empty);
+                if ao.len() != an.len() {
+                    diff.push(
+                        &p,
+                        format!("{} schemas -> {} schemas", ao.len(), an.len()),
+                        !(k == "anyOf" && an.len() > ao.len()),
+                    );
+                } else {
+                    for (i, (x, y)) in ao.iter().zip(an.iter()).enumerate() {
+                        diff_schemas(x, y, &format!("{}/{}", p, i), diff);
+                    }
+                }
+            }
+            _ => match (o, n) {
+                (Some(o), Some(n)) => diff.push(&p, format!("{} -> {}", o, n), true),
+                (None, Some(n)) => diff.push(&p, format!("{} added", n), true),
+                (Some(o), None) => diff.push(&p, format!("{} removed", o), true),
+                (None, None) => (),
+            },
+        }
+    }
+}
+
+/// The members of "properties": a removed property is breaking only if
+/// `closed` (the additional properties are not allowed), and an added one
+/// only if not `closed` (its values were allowed and not constrained before)
+fn diff_properties(old: &Value, new: &Value, path: &str, closed: bool, diff: &mut ExtensionDiff) {
+    let empty = Map::new();
+    let mold = old.as_object().unwrap_or(&empty);
+    let mnew = new.as_object().unwrap_or(&empty);
+    for (k, s) in mold {
+        let p = format!("{}/{}", path, k);
+        match mnew.get(k) {
+            Some(s2) => diff_schemas(s, s2, &p, diff),
+            None => diff.push(&p, "removed".to_string(), closed),
+        }
+    }
+    for k in mnew.keys() {
+        if !mold.contains_key(k) {
+            diff.push(&format!("{}/{}", path, k), "added".to_string(), !closed);
+        }
+    }
+}
+
+fn diff_bound(
+    o: Option<&Value>,
+    n: Option<&Value>,
+    path: &str,
+    is_min: bool,
+    diff: &mut ExtensionDiff,
+) {
+    let (o, n) = (o.and_then(|x| x.as_f64()), n.and_then(|x| x.as_f64()));
+    let breaking = match (o, n) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(o), Some(n)) => {
+            if is_min {
+                n > o
+            } else {
+                n < o
+            }
+        }
+    };
+    let show = |x: Option<f64>| x.map_or("none".to_string(), |x| x.to_string());
+    diff.push(path, format!("{} -> {}", show(o), show(n)), breaking);
+}
+
+fn as_strings(v: Option<&Value>) -> Vec<String> {
+    match v {
+        Some(Value::String(s)) => vec![s.to_string()],
+        Some(Value::Array(a)) => a
+            .iter()
+            .filter_map(|x| x.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,264 @@
+//! Validation of a CityJSON Extension file (the `.ext.json`), before it is
+//! used to validate CityJSON files.
+//!
+//! The Extension is validated against the schema of Extensions (there is none
+//! for v1.0 Extensions, those without "versionCityJSON"), then its `"$ref"`
+//! must point to definitions that exist (those to other Extensions are not
+//! checked), the new names must start with a "+", and each extra schema must
+//! compile.
+
+use crate::schemaset;
+use crate::EXTENSION_FIXED_NAMES;
+use indexmap::IndexMap;
+use jsonschema::{Draft, JSONSchema};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// One error in an Extension file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionError {
+    /// The check that failed: "json_syntax", "schema", "refs", "names" or "compile"
+    pub check: String,
+    /// JSON path in the Extension file, eg "/extraCityObjects/+NoiseBuilding"
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ExtensionError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            fmt.write_str(&self.message)
+        } else {
+            write!(fmt, "{} [path:{}]", self.message, self.path)
+        }
+    }
+}
+
+/// The result of [`validate_extension_str`]
+#[derive(Debug, Default)]
+pub struct ExtensionReport {
+    /// The "versionCityJSON" of the Extension (if it is a supported one)
+    pub version_cityjson: Option<String>,
+    pub errors: Vec<ExtensionError>,
+}
+
+impl ExtensionReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The errors grouped by check, in the order the checks are performed
+    pub fn errors_by_check(&self) -> IndexMap<String, Vec<String>> {
+        let mut re: IndexMap<String, Vec<String>> = IndexMap::new();
+        for e in &self.errors {
+            re.entry(e.check.clone()).or_default().push(e.to_string());
+        }
+        re
+    }
+
+    fn push(&mut self, check: &str, path: &str, message: String) {
+        self.errors.push(ExtensionError {
+            check: check.to_string(),
+            path: path.to_string(),
+            message,
+        });
+    }
+}
+
+/// Validates the content of a CityJSON Extension file.
+/// ```rust
+/// let s = std::fs::read_to_string("./schemas/extensions/20/noise.ext.json").unwrap();
+/// let re = cjval::validate_extension_str(&s);
+/// assert!(re.is_valid());
+/// for e in &re.errors {
+///     println!("{}: {}", e.check, e);
+/// }
+/// ```
+pub fn validate_extension_str(s: &str) -> ExtensionReport {
+    let mut report = ExtensionReport::default();
+    let j: Value = match serde_json::from_str(s) {
+        Ok(j) => j,
+        Err(e) => {
+            report.push("json_syntax", "", e.to_string());
+            return report;
+        }
+    };
+    let (version_file, schema_str) = match j["versionCityJSON"].as_str() {
+        Some("1.1") => (
+            11,
+            Some(include_str!(
+                "../schemas/extensions/11/extension.schema.json"
+            )),
+        ),
+        Some("2.0") => (
+            20,
+            Some(include_str!(
+                "../schemas/extensions/20/extension.schema.json"
+            )),
+        ),
+        //-- v1.0 Extensions have no "type" and no "versionCityJSON" (and no schema)
+        None if j.is_object() && j.get("type").is_none() => (10, None),
+        _ => {
+            report.push(
+                "schema",
+                "/versionCityJSON",
+                "the \"versionCityJSON\" property must be \"1.1\" or \"2.0\"".to_string(),
+            );
+            return report;
+        }
+    };
+    report.version_cityjson = match version_file {
+        10 => Some("1.0".to_string()),
+        _ => j["versionCityJSON"].as_str().map(|v| v.to_string()),
+    };
+
+    //-- 1. the schema of Extensions
+    if let Some(schema_str) = schema_str {
+        let schema: Value = serde_json::from_str(schema_str).unwrap();
+        let compiled = JSONSchema::options()
+            .with_draft(Draft::Draft7)
+            .compile(&schema)
+            .expect("A valid schema");
+        if let Err(errors) = compiled.validate(&j) {
+            for error in errors {
+                report.push(
+                    "schema",
+                    &error.instance_path.to_string(),
+                    error.to_string(),
+                );
+            }
+        };
+    }
+    let (_, core) = schemaset::core_values(version_file).unwrap();
+    let mut docs: Vec<(String, &Value)> = Vec::new();
+    for (name, d) in core.iter() {
+        if version_file == 10 {
+            //-- v1.0 Extensions are in "extensions/"
+            docs.push((format!("../{}", name), d));
+        }
+        docs.push((name.to_string(), d));
+    }
+
+    //-- 2. the "$ref" must point to definitions that exist
+    validate_refs(&j, &j, "", &docs, &mut report);
+
+    //-- 3. the new CityObjects, properties, attributes and semantic surfaces start with "+"
+    for (path, name, _) in extra_schemas(&j) {
+        if !name.starts_with('+') {
+            report.push(
+                "names",
+                &path,
+                format!("'{}' must start with a \"+\"", name),
+            );
+        }
+    }
+
+    //-- 4. each extra schema must compile (only if the rest is valid),
+    //-- those referencing other Extensions cannot be compiled alone
+    if report.is_valid() {
+        for (path, _, schema) in extra_schemas(&j) {
+            let mut schema = schemaset::object_schema(schema);
+            schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+            schema["$id"] = json!(schemaset::extension_schema_id(version_file));
+            for (k, v) in j.as_object().unwrap() {
+                if !EXTENSION_FIXED_NAMES.contains(&k.as_str()) {
+                    schema[k] = v.clone();
+                }
+            }
+            if !schemaset::external_refs(&schema, version_file).is_empty() {
+                continue;
+            }
+            if let Some(Err(e)) = schemaset::try_compile_extension(version_file, &schema, &[]) {
+                report.push(
+                    "compile",
+                    &path,
+                    format!("the schema doesn't compile: {}", e),
+                );
+            }
+        }
+    }
+    report
+}
+
+/// The extra schemas of an Extension: (path, name, schema)
+fn extra_schemas(j: &Value) -> Vec<(String, String, &Value)> {
+    let mut re: Vec<(String, String, &Value)> = Vec::new();
+    for p in [
+        "extraCityObjects",
+        "extraRootProperties",
+        "extraSemanticSurfaces",
+    ] {
+        if let Some(x) = j[p].as_object() {
+            for (k, s) in x {
+                re.push((format!("/{}/{}", p, k), k.to_string(), s));
+            }
+        }
+    }
+    if let Some(x) = j["extraAttributes"].as_object() {
+        for (cotype, atts) in x {
+            if let Some(atts) = atts.as_object() {
+                for (k, s) in atts {
+                    re.push((
+                        format!("/extraAttributes/{}/{}", cotype, k),
+                        k.to_string(),
+                        s,
+                    ));
+                }
+            }
+        }
+    }
+    re
+}
+
+fn validate_refs(
+    j: &Value,
+    root: &Value,
+    path: &str,
+    docs: &[(String, &Value)],
+    report: &mut ExtensionReport,
+) {
+    match j {
+        Value::Object(o) => {
+            for (k, v) in o {
+                let p = format!("{}/{}", path, k);
+                match (k.as_str(), v.as_str()) {
+                    ("$ref", Some(r)) => validate_one_ref(r, root, &p, docs, report),
+                    _ => validate_refs(v, root, &p, docs, report),
+                }
+            }
+        }
+        Value::Array(a) => {
+            for (i, v) in a.iter().enumerate() {
+                validate_refs(v, root, &format!("{}/{}", path, i), docs, report);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn validate_one_ref(
+    r: &str,
+    root: &Value,
+    path: &str,
+    docs: &[(String, &Value)],
+    report: &mut ExtensionReport,
+) {
+    let (file, pointer) = r.split_once('#').unwrap_or((r, ""));
+    if file.is_empty() {
+        //-- internal ref, eg "#/definitions/measure"
+        if root.pointer(pointer).is_none() {
+            report.push("refs", path, format!("\"{}\" not found", r));
+        }
+        return;
+    }
+    match docs.iter().find(|(name, _)| *name == file) {
+        Some((_, doc)) => {
+            if doc.pointer(pointer).is_none() {
+                report.push("refs", path, format!("\"{}\" not found in {}", r, file));
+            }
+        }
+        //-- another Extension, it cannot be checked here
+        None if file.ends_with(".ext.json") => (),
+        None => report.push("refs", path, format!("\"{}\" not found", r)),
+    }
+}
@@ -15,6 +15,8 @@
 //!   1. *semantics_arrays*: checks if the arrays for the semantics in the geometries have the same shape as that of the geometry and if the values are consistent
 //!   1. *textures*: checks if the arrays for the textures are coherent (if the vertices exist + if the texture  exists)
 //!   1. *materials*: checks if the arrays for the materials are coherent with the geometry objects and if the material exists
+//!   1. *attribute_schemas*: if JSON Schemas are given for the attributes of some CityObject types (with [`CJValidator::add_attribute_schema`]), validates the `"attributes"` of those CityObjects
+//!   1. *profile*: if a validation [`Profile`] is given, checks the data-quality requirements (required geometries/LoDs and attributes, unique and allowed values, etc.)
 //!
 //! It also verifies the following, these are not errors but warnings since the file is still considered valid and usable, but they can make the file larger and some parsers might not understand all the properties:
 //!
@@ -22,6 +24,9 @@
 //!   1. *duplicate_vertices*: duplicated vertices in `"vertices"` are allowed, but they take up space and decrease the topological relationships explicitly in the file. If there are any, [cjio](https://github.com/cityjson/cjio) has the operator `clean` to fix this automatically.
 //!   1. *unused_vertices*: vertices that are not referenced in the file, they take extra space. If there are any, [cjio](https://github.com/cityjson/cjio) has the operator `clean` to fix this automatically.
 //!
+//! Each check can be disabled and its severity changed with [`ValidationOptions`], and project-specific checks can be added with [`CJValidator::add_check`] (see [`Check`]).
+//! By default the validation stops after errors in the schema or the Extensions (to avoid cascading errors), unless the exhaustive mode is used.
+//!
 //! ## A library + 2 binaries
 //!
 //! `cjval` is a library and has 2 different binaries:
@@ -55,15 +60,46 @@
 //!
 //!
 
+mod baseline;
+mod check;
+mod equivalence;
+mod extdiff;
+mod extension;
+mod options;
+mod parse;
+mod profile;
+mod resolver;
+mod schemaset;
+
+pub use baseline::{Baseline, BaselineEntry};
+pub use check::{Check, Diagnostic, ValidationContext};
+use check::{CheckRef, ErrorList};
+pub use equivalence::{Equivalence, EquivalenceReport};
+pub use extdiff::{diff_extensions_str, ExtensionChange, ExtensionDiff};
+pub use extension::{validate_extension_str, ExtensionError, ExtensionReport};
+pub use options::{Severity, ValidationOptions};
+pub use parse::Location;
+pub use profile::{CityObjectRules, GeometryRule, Profile, ValueRule, ValueType};
+#[cfg(feature = "http")]
+pub use resolver::HttpResolver;
+pub use resolver::{
+    CacheResolver, ExtensionRef, ExtensionResolver, ExtensionStatus, LocalDirResolver,
+    MemoryResolver,
+};
+pub use schemaset::SchemaSet;
+
 use anyhow::{anyhow, Result};
 use indexmap::IndexMap;
-use jsonschema::{Draft, JSONSchema};
+use jsonschema::JSONSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 // #-- ERRORS
 //  # schema
@@ -79,7 +115,9 @@ use std::fmt;
 //  # duplicate_vertices
 //  # unused_vertices
 
-static EXTENSION_FIXED_NAMES: [&str; 6] = [
+static EXTENSION_FIXED_NAMES: [&str; 8] = [
+    "$schema",
+    "$id",
     "type",
     "name",
     "url",
@@ -90,12 +128,19 @@ static EXTENSION_FIXED_NAMES: [&str; 6] = [
 
 /// Summary of a validation. It is possible that a validation check has not
 /// been performed because other checks returned errors (we do not want to
-/// have cascading errors).
+/// have cascading errors), or because it is disabled in the
+/// [`ValidationOptions`].
 #[derive(Debug)]
 pub struct ValSummary {
     status: Option<bool>,
     errors: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+    more: usize,
+    ignored: Vec<Diagnostic>,
+    nignored: usize,
     warning: bool,
+    disabled: bool,
+    cascading: bool,
 }
 
 impl ValSummary {
@@ -104,9 +149,24 @@ impl ValSummary {
         ValSummary {
             status: None,
             errors: l,
+            diagnostics: Vec::new(),
+            more: 0,
+            ignored: Vec::new(),
+            nignored: 0,
             warning: false,
+            disabled: false,
+            cascading: false,
         }
     }
+    fn with_severity(severity: Severity) -> ValSummary {
+        let mut vs = ValSummary::new();
+        match severity {
+            Severity::Error => (),
+            Severity::Warning => vs.set_as_warning(),
+            Severity::Off => vs.disabled = true,
+        }
+        vs
+    }
     fn set_validity(&mut self, b: bool) {
         self.status = Some(b);
     }
@@ -117,6 +177,15 @@ impl ValSummary {
     pub fn is_warning(&self) -> bool {
         self.warning
     }
+    /// Returns true if the check is disabled (it is not performed)
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+    /// Returns true if the errors may be caused by the errors of an earlier
+    /// check (only in the exhaustive mode, see [`ValidationOptions::set_exhaustive`])
+    pub fn may_be_cascading(&self) -> bool {
+        self.cascading
+    }
     /// Returns true if valid, false if not (and also false if not performed)
     pub fn is_valid(&self) -> bool {
         if self.status == Some(true) {
@@ -139,13 +208,38 @@ impl ValSummary {
         }
     }
     fn add_error(&mut self, e: String) {
-        self.errors.push(e);
+        self.add_diagnostic(Diagnostic::new(&e));
+    }
+    fn add_diagnostic(&mut self, d: Diagnostic) {
+        self.errors.push(d.to_string());
+        self.diagnostics.push(d);
         self.set_validity(false);
     }
-    /// Returns a reference to the list of errors
+    /// Returns a reference to the list of errors. The identical errors are
+    /// grouped ("... (12 times)"), and after the limit of
+    /// [`ValidationOptions::set_error_limit`] the others are only counted
+    /// ("... and 1,203,442 more")
     pub fn get_errors(&self) -> &Vec<String> {
         &self.errors
     }
+    /// Returns the number of errors (also those grouped or not listed)
+    pub fn get_error_count(&self) -> usize {
+        self.diagnostics.iter().map(|d| d.count).sum::<usize>() + self.more
+    }
+    /// Returns the errors as [`Diagnostic`]s (with the CityObject id and the
+    /// JSON Pointer if the check gives them)
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+    /// Returns the errors that are ignored because they are in the
+    /// [`Baseline`] (they are not in the errors), up to the error limit
+    pub fn get_ignored(&self) -> &[Diagnostic] {
+        &self.ignored
+    }
+    /// Returns the number of errors ignored because of the [`Baseline`]
+    pub fn get_ignored_count(&self) -> usize {
+        self.nignored
+    }
 }
 
 impl fmt::Display for ValSummary {
@@ -199,13 +293,6 @@ struct TextureMSol {
     values: Vec<Vec<Vec<Vec<Vec<Option<usize>>>>>>,
 }
 
-#[allow(non_snake_case)]
-#[derive(Deserialize, PartialEq)]
-struct Doc {
-    #[serde(with = "::serde_with::rust::maps_duplicate_key_is_error")]
-    CityObjects: HashMap<String, Value>,
-}
-
 pub fn get_cityjson_schema_all_versions() -> Vec<String> {
     let mut l: Vec<String> = Vec::new();
     //-- v1.0
@@ -227,21 +314,32 @@ pub fn get_cityjson_schema_all_versions() -> Vec<String> {
 #[derive(Debug)]
 pub struct CJValidator {
     j: Value,
-    jschema_cj: Value,
-    jschema_cjf: Value,
+    jheader: Value,
+    schemas: Arc<SchemaSet>,
     jexts: Vec<Value>,
+    exts_key: u64,
     json_syntax_error: Option<String>,
     duplicate_keys: bool,
+    locations: parse::Locations,
+    keep_locations: bool,
     is_cityjson: bool,
     is_cjfeature: bool,
     version_file: i32,
     version_schema: String,
+    feature_line: Option<usize>,
+    options: ValidationOptions,
+    profile: Option<Profile>,
+    profile_seen: profile::UniqueValues,
+    attribute_schemas: IndexMap<String, Arc<JSONSchema>>,
+    checks: Vec<Arc<dyn Check>>,
+    baseline: Option<Baseline>,
 }
 
 impl CJValidator {
     /// Creates a CJValidator from a &str.
     /// Will not return an error here if the &str is not a JSON,
     /// only when validate() is called can you see that error.
+    /// The compiled schemas are shared by all the validators (see [`SchemaSet::global`]).
     /// ```rust
     /// use cjval::CJValidator;
     /// let s1 = std::fs::read_to_string("./data/cube.city.json")
@@ -249,24 +347,91 @@ impl CJValidator {
     /// let v = CJValidator::from_str(&s1);
     /// ```
     pub fn from_str(str_dataset: &str) -> Self {
+        CJValidator::from_str_with_schemas(str_dataset, SchemaSet::global())
+    }
+
+    /// Creates a CJValidator from a &str, the schemas (and the Extension
+    /// schemas) are compiled and cached in the given SchemaSet.
+    pub fn from_str_with_schemas(str_dataset: &str, schemas: Arc<SchemaSet>) -> Self {
+        CJValidator::from_parsed(parse::parse_str(str_dataset, false), schemas, false)
+    }
+
+    /// Same as [`CJValidator::from_str`], but the locations in the input of
+    /// the values are kept, so that the diagnostics have their location (see
+    /// [`Diagnostic::location`]); they are kept for the CityJSONFeatures
+    /// added too. For a large file this takes a lot of memory.
+    /// ```rust
+    /// use cjval::CJValidator;
+    /// let s1 = std::fs::read_to_string("./data/cube.city.json").unwrap();
+    /// let v = CJValidator::from_str_with_locations(&s1);
+    /// let re = v.validate();
+    /// ```
+    pub fn from_str_with_locations(str_dataset: &str) -> Self {
+        CJValidator::from_parsed(
+            parse::parse_str(str_dataset, true),
+            SchemaSet::global(),
+            true,
+        )
+    }
+
+    /// Creates a CJValidator by reading a CityJSON from a reader (eg a file),
+    /// without having to keep the whole file in memory as a String.
+    /// ```rust
+    /// use cjval::CJValidator;
+    /// let f = std::fs::File::open("./data/cube.city.json")
+    ///         .expect("Couldn't open CityJSON file");
+    /// let v = CJValidator::from_reader(std::io::BufReader::new(f));
+    /// ```
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Self {
+        CJValidator::from_reader_with_schemas(reader, SchemaSet::global())
+    }
+
+    /// Same as [`CJValidator::from_reader`] but with a given SchemaSet.
+    pub fn from_reader_with_schemas<R: std::io::Read>(reader: R, schemas: Arc<SchemaSet>) -> Self {
+        CJValidator::from_parsed(parse::parse_reader(reader, false), schemas, false)
+    }
+
+    /// Same as [`CJValidator::from_reader`], but the locations in the input
+    /// of the values are kept (see [`CJValidator::from_str_with_locations`]).
+    pub fn from_reader_with_locations<R: std::io::Read>(reader: R) -> Self {
+        CJValidator::from_parsed(parse::parse_reader(reader, true), SchemaSet::global(), true)
+    }
+
+    fn from_parsed(
+        re: std::result::Result<parse::Parsed, serde_json::Error>,
+        schemas: Arc<SchemaSet>,
+        keep_locations: bool,
+    ) -> Self {
         let l: Vec<Value> = Vec::new();
         let mut v = CJValidator {
             j: json!(null),
-            jschema_cj: json!(null),
-            jschema_cjf: json!(null),
+            jheader: json!(null),
+            schemas,
             jexts: l,
+            exts_key: 0,
             json_syntax_error: None,
             duplicate_keys: false,
+            locations: parse::Locations::default(),
+            keep_locations,
             is_cityjson: true,
             is_cjfeature: false,
             version_file: 0,
             version_schema: "-1".to_string(),
+            feature_line: None,
+            options: ValidationOptions::default(),
+            profile: None,
+            profile_seen: HashMap::new(),
+            attribute_schemas: IndexMap::new(),
+            checks: Vec::new(),
+            baseline: None,
         };
-        //-- parse the dataset and convert to JSON
-        let re = serde_json::from_str(&str_dataset);
+        //-- the dataset is parsed only once, the duplicate keys in the
+        //-- "CityObjects" are detected and the locations kept at the same time
         match re {
-            Ok(j) => {
-                v.j = j;
+            Ok(p) => {
+                v.j = p.j;
+                v.duplicate_keys = p.duplicate_keys;
+                v.locations = p.locations;
                 // TODO: what if j.is_null() is true?
             }
             Err(e) => v.json_syntax_error = Some(e.to_string()),
@@ -276,54 +441,55 @@ impl CJValidator {
             //-- check cityjson version
             if v.j["version"] == "2.0" {
                 v.version_file = 20;
-                let schema_str = include_str!("../schemas/20/cityjson.min.schema.json");
-                v.jschema_cj = serde_json::from_str(schema_str).unwrap();
-                let vs = &v.jschema_cj["$id"].to_string();
-                v.version_schema = vs.get(34..39).unwrap().to_string();
-                //-- for CityJSONFeature
-                let schemaf_str = include_str!("../schemas/20/cityjsonfeature.min.schema.json");
-                v.jschema_cjf = serde_json::from_str(schemaf_str).unwrap();
             } else if v.j["version"] == "1.1" {
                 v.version_file = 11;
-                let schema_str = include_str!("../schemas/11/cityjson.min.schema.json");
-                v.jschema_cj = serde_json::from_str(schema_str).unwrap();
-                let vs = &v.jschema_cj["$id"].to_string();
-                v.version_schema = vs.get(34..39).unwrap().to_string();
-                //-- for CityJSONFeature
-                let schemaf_str = include_str!("../schemas/11/cityjsonfeature.min.schema.json");
-                v.jschema_cjf = serde_json::from_str(schemaf_str).unwrap();
             } else if v.j["version"] == "1.0" {
                 v.version_file = 10;
-                let schema_str = include_str!("../schemas/10/cityjson.min.schema.json");
-                v.jschema_cj = serde_json::from_str(schema_str).unwrap();
-                v.version_schema = "1.0.3".to_string();
+            }
+            if let Some(vs) = v.schemas.version_schema(v.version_file) {
+                v.version_schema = vs;
             }
         } else {
             v.is_cityjson = false;
         }
-        //-- check for duplicate keys in CO object, Doc is the struct above
-        //-- used for identifying duplicate keys
-        if v.json_syntax_error.is_none() {
-            let re: Result<Doc, _> = serde_json::from_str(&str_dataset);
-            if re.is_err() {
-                v.duplicate_keys = true;
-            }
-        }
         v
     }
 
+    /// Same as [`CJValidator::from_str_cjfeature`] for the CityJSONFeature at
+    /// the line `line` of a CityJSONSeq stream, the errors of the Extensions
+    /// are reported with the feature id and that line.
+    pub fn from_str_cjfeature_at(&mut self, str_cjf: &str, line: usize) -> Result<(), String> {
+        self.from_str_cjfeature(str_cjf)?;
+        self.feature_line = Some(line);
+        Ok(())
+    }
+
     pub fn from_str_cjfeature(&mut self, str_cjf: &str) -> Result<(), String> {
         //-- parse the cjf and convert to JSON
-        let re: Result<Value, _> = serde_json::from_str(&str_cjf);
+        let re = parse::parse_str(str_cjf, self.keep_locations);
         if re.is_err() {
             return Err(re.err().unwrap().to_string());
         }
-        let j: Value = re.unwrap();
+        let p = re.unwrap();
+        let j: Value = p.j;
         if j["type"] != "CityJSONFeature" {
             return Err("Not a CityJSONFeature object".to_string());
         }
-        self.j = j;
+        self.duplicate_keys = p.duplicate_keys;
+        self.locations = p.locations;
+        //-- the "unique" values of the profile must be unique in the stream
+        if let Some(profile) = &self.profile {
+            profile::collect_unique_values(profile, &self.j, &mut self.profile_seen);
+        }
+        //-- keep the 1st line (the CityJSON object): its "transform",
+        //-- "appearance" and "geometry-templates" apply to all the features
+        if !self.is_cjfeature {
+            self.jheader = std::mem::replace(&mut self.j, j);
+        } else {
+            self.j = j;
+        }
         self.is_cjfeature = true;
+        self.feature_line = None;
         // println!("{:?}", self.version_file);
         // if self.version == "2.0" {
         //     v.version_file = 20;
@@ -343,7 +509,7 @@ impl CJValidator {
     }
 
     /// Add the content (&str) of an Extension.
-    /// The library cannot download automatically the Extensions.
+    /// To fetch the Extensions listed in the file, use [`CJValidator::resolve_extensions`].
     /// ```rust
     /// use cjval::CJValidator;
     /// let sdata = std::fs::read_to_string("./data/cube.city.json")
@@ -358,44 +524,176 @@ impl CJValidator {
         if re.is_err() {
             return Err(anyhow!(re.err().unwrap().to_string()));
         }
-        self.jexts.push(re.unwrap());
+        let j = re.unwrap();
+        if !j.is_object() {
+            return Err(anyhow!("The Extension is not a JSON object"));
+        }
+        //-- the key of the Extensions loaded, for the cache of compiled schemas
+        let mut h = DefaultHasher::new();
+        (self.exts_key, ext_schema_str).hash(&mut h);
+        self.exts_key = h.finish();
+        self.jexts.push(j);
+        Ok(())
+    }
+
+    /// Adds JSON Schemas for the `"attributes"` of CityObjects (also those
+    /// without a "+"), eg to validate against a data dictionary. The &str is
+    /// a JSON object with the CityObject types as keys and the schemas of
+    /// their `"attributes"` as values.
+    /// The errors are reported in the check *attribute_schemas*, a schema
+    /// that cannot be compiled is an error here.
+    /// ```rust
+    /// use cjval::CJValidator;
+    /// let sdata = std::fs::read_to_string("./data/cube.city.json").unwrap();
+    /// let mut val = CJValidator::from_str(&sdata);
+    /// let s = r#"{"+GenericCityObject": {"required": ["function"]}}"#;
+    /// val.add_attribute_schemas_from_str(s).unwrap();
+    /// ```
+    pub fn add_attribute_schemas_from_str(&mut self, schemas_str: &str) -> Result<()> {
+        let j: Value = serde_json::from_str(schemas_str).map_err(|e| anyhow!(e.to_string()))?;
+        let Some(m) = j.as_object() else {
+            return Err(anyhow!(
+                "The attribute schemas are not a JSON object (CityObject type -> schema)"
+            ));
+        };
+        for (cotype, schema) in m {
+            self.add_attribute_schema(cotype, schema)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the JSON Schema for the `"attributes"` of one CityObject type,
+    /// it replaces the previous one of that type. The schema is compiled
+    /// right away, an error is returned if it cannot be.
+    pub fn add_attribute_schema(&mut self, cotype: &str, schema: &Value) -> Result<()> {
+        //-- the "$ref" to the CityJSON schemas are resolved like in the
+        //-- Extensions (with v2.0 if the version of the file is unknown)
+        let version = match schemaset::core_documents(self.version_file) {
+            Some(_) => self.version_file,
+            None => 20,
+        };
+        let mut s = schemaset::object_schema(schema);
+        s["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+        if let Some(id) = schemaset::extension_schema_id(version) {
+            s["$id"] = json!(id);
+        }
+        match schemaset::try_compile_extension(version, &s, &[]) {
+            Some(Ok(compiled)) => {
+                self.attribute_schemas
+                    .insert(cotype.to_string(), Arc::new(compiled));
+                Ok(())
+            }
+            Some(Err(e)) => Err(anyhow!(
+                "The attribute schema of '{}' cannot be used: {}",
+                cotype,
+                e
+            )),
+            None => Err(anyhow!("CityJSON v{} has no schemas", version)),
+        }
+    }
+
+    /// Sets the rule set used by [`CJValidator::validate`]: which checks are
+    /// performed and their severity. The options are kept when a
+    /// CityJSONFeature is added with [`CJValidator::from_str_cjfeature`].
+    pub fn set_options(&mut self, options: ValidationOptions) {
+        self.options = options;
+    }
+
+    /// Returns the rule set used by [`CJValidator::validate`]
+    pub fn get_options(&self) -> &ValidationOptions {
+        &self.options
+    }
+
+    /// Sets the validation profile (data-quality requirements beyond the
+    /// specifications), its errors are reported in the check *profile*.
+    /// The profile is kept when a CityJSONFeature is added, and the "unique"
+    /// values must then be unique among all the features added.
+    /// ```rust
+    /// use cjval::{CJValidator, Profile};
+    /// let s1 = std::fs::read_to_string("./data/cube.city.json").unwrap();
+    /// let mut v = CJValidator::from_str(&s1);
+    /// let p = r#"{"cityobjects": {"Building": {"geometry": [{"type": "Solid", "lod": "2.2"}]}}}"#;
+    /// v.set_profile(Profile::from_json_str(p).unwrap());
+    /// let re = v.validate();
+    /// for e in re["profile"].get_errors() {
+    ///     println!("{}", e);
+    /// }
+    /// ```
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = Some(profile);
+        self.profile_seen.clear();
+    }
+
+    /// Returns the validation profile, if there is one
+    pub fn get_profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Adds a check, performed by [`CJValidator::validate`] after the built-in
+    /// ones of the same severity (see [`Check`]). An error is returned if a
+    /// check with that name already exists. The checks are kept when a
+    /// CityJSONFeature is added.
+    pub fn add_check<C: Check + 'static>(&mut self, check: C) -> Result<(), String> {
+        let name = check.name();
+        if name == "json_syntax"
+            || check::builtin_checks().any(|c| c.name() == name)
+            || self.checks.iter().any(|c| c.name() == name)
+        {
+            return Err(format!("a check named '{}' already exists", name));
+        }
+        self.checks.push(Arc::new(check));
         Ok(())
     }
 
+    /// The names of the checks performed by [`CJValidator::validate`]: the
+    /// built-in ones and those added, eg to verify the names in the
+    /// [`ValidationOptions`]
+    pub fn get_check_names(&self) -> Vec<String> {
+        let mut re = vec!["json_syntax".to_string()];
+        re.extend(check::builtin_checks().map(|c| c.name().to_string()));
+        re.extend(self.checks.iter().map(|c| c.name().to_string()));
+        re
+    }
+
+    /// Sets the known issues to ignore: they are not reported anymore by
+    /// [`CJValidator::validate`] (see [`ValSummary::get_ignored`]), only the
+    /// new ones are. The baseline is kept when a CityJSONFeature is added.
+    /// ```rust
+    /// use cjval::{Baseline, CJValidator};
+    /// let s1 = std::fs::read_to_string("./data/cube.city.json").unwrap();
+    /// let mut v = CJValidator::from_str(&s1);
+    /// let b = r#"{"ignore": [{"check": "unused_vertices"}]}"#;
+    /// v.set_baseline(Baseline::from_json_str(b).unwrap());
+    /// let re = v.validate();
+    /// assert!(!re["unused_vertices"].has_errors());
+    /// ```
+    pub fn set_baseline(&mut self, baseline: Baseline) {
+        self.baseline = Some(baseline);
+    }
+
+    /// Returns the baseline, if there is one
+    pub fn get_baseline(&self) -> Option<&Baseline> {
+        self.baseline.as_ref()
+    }
+
     /// Returns true if the CityJSON/Feature does not contain errors.
     /// False otherwise.
     pub fn is_valid(&self) -> bool {
         let valsumm = self.validate();
-        if valsumm["json_syntax"].has_errors() {
-            return false;
-        }
-        if valsumm["schema"].has_errors() {
-            return false;
-        }
-        if valsumm["extensions"].has_errors() {
-            return false;
-        }
-        if valsumm["parents_children_consistency"].has_errors() {
-            return false;
-        }
-        if valsumm["wrong_vertex_index"].has_errors() {
-            return false;
-        }
-        if valsumm["semantics_arrays"].has_errors() {
-            return false;
-        }
-        if valsumm["materials"].has_errors() {
-            return false;
-        }
-        if valsumm["textures"].has_errors() {
-            return false;
-        }
-        true
+        !has_errors(&valsumm)
     }
 
     /// The function to performs all the checks (errors+warnings).
     /// Return a IndexMap (a HashMap where keys are ordered) containing
     /// the check name and a ValSummary.
+    /// The checks performed and their severity are those of the
+    /// [`ValidationOptions`] (see [`CJValidator::set_options`]).
+    /// The validation stops after errors in the schema or the Extensions, and
+    /// the warnings (those of the options, eg a warning set as an error is
+    /// checked with the errors) are checked only if there are no errors,
+    /// unless the exhaustive mode is used.
+    /// If the options change the severity of a check that does not exist (eg
+    /// a typo), this is an error reported in *options*.
     /// ```rust
     /// use cjval::CJValidator;
     /// let s1 = std::fs::read_to_string("./data/many.json")
@@ -408,28 +706,49 @@ impl CJValidator {
     /// }
     /// ```
     pub fn validate(&self) -> IndexMap<String, ValSummary> {
-        let mut w1 = ValSummary::new();
-        w1.set_as_warning();
-        let mut w2 = ValSummary::new();
-        w2.set_as_warning();
-        let mut w3 = ValSummary::new();
-        w3.set_as_warning();
-        let mut vsum = IndexMap::from([
-            ("json_syntax".to_string(), ValSummary::new()),
-            ("schema".to_string(), ValSummary::new()),
-            ("extensions".to_string(), ValSummary::new()),
-            (
-                "parents_children_consistency".to_string(),
-                ValSummary::new(),
-            ),
-            ("wrong_vertex_index".to_string(), ValSummary::new()),
-            ("semantics_arrays".to_string(), ValSummary::new()),
-            ("textures".to_string(), ValSummary::new()),
-            ("materials".to_string(), ValSummary::new()),
-            ("extra_root_properties".to_string(), w1),
-            ("duplicate_vertices".to_string(), w2),
-            ("unused_vertices".to_string(), w3),
-        ]);
+        let severity = |c: &dyn Check| -> Severity {
+            self.options
+                .rules()
+                .get(c.name())
+                .copied()
+                .unwrap_or(c.severity())
+        };
+        //-- the built-in checks and the added ones: the errors then the warnings
+        //-- (with the severities of the options)
+        let mut all: Vec<CheckRef> = check::builtin_checks().map(CheckRef::Builtin).collect();
+        all.extend(self.checks.iter().map(|c| CheckRef::Added(c.as_ref())));
+        let (warnings, errors): (Vec<CheckRef>, Vec<CheckRef>) = all
+            .into_iter()
+            .partition(|c| severity(c.check()) == Severity::Warning);
+        let checks: Vec<CheckRef> = errors.into_iter().chain(warnings).collect();
+        let mut vsum: IndexMap<String, ValSummary> = IndexMap::new();
+        vsum.insert(
+            "json_syntax".to_string(),
+            ValSummary::with_severity(Severity::Error),
+        );
+        //-- the rules of the options that are not checks (eg a typo)
+        let names = self.get_check_names();
+        let unknown: Vec<&String> = self
+            .options
+            .rules()
+            .keys()
+            .filter(|c| !names.contains(c))
+            .collect();
+        if !unknown.is_empty() {
+            let mut s = ValSummary::with_severity(Severity::Error);
+            for c in unknown {
+                s.add_error(format!(
+                    "Unknown check '{}' in the options (must be one of: {})",
+                    c,
+                    names[1..].join(", ")
+                ));
+            }
+            vsum.insert("options".to_string(), s);
+        }
+        for c in &checks {
+            let c = c.check();
+            vsum.insert(c.name().to_string(), ValSummary::with_severity(severity(c)));
+        }
 
         //-- json_syntax
         match &self.json_syntax_error {
@@ -442,151 +761,157 @@ impl CJValidator {
             None => vsum.get_mut("json_syntax").unwrap().set_validity(true),
         }
 
-        //-- schema
-        let mut re = self.schema();
-        match re {
-            Ok(_) => vsum.get_mut("schema").unwrap().set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("schema").unwrap().add_error(err);
+        //-- in the exhaustive mode, the checks after an error are performed
+        //-- but their errors are marked as possibly cascading
+        let exhaustive = self.options.is_exhaustive();
+        let mut cascading = false;
+        let mut warnings_reached = false;
+        let ctx = ValidationContext::new(self);
+        let baseline = self.baseline.as_ref().map(baseline::BaselineIndex::new);
+        for cr in checks {
+            let c = cr.check();
+            //-- warnings : only do if no errors so far
+            if !warnings_reached && severity(c) == Severity::Warning {
+                warnings_reached = true;
+                if has_errors(&vsum) {
+                    if !exhaustive {
+                        return vsum;
+                    }
+                    cascading = true;
                 }
-                return vsum;
             }
-        }
-        if self.duplicate_keys == true {
-            vsum.get_mut("schema")
-                .unwrap()
-                .add_error("Duplicate keys in 'CityObjects'".to_string());
-            return vsum;
-        }
-
-        //-- extensions
-        re = self.validate_extensions();
-        match re {
-            Ok(_) => vsum.get_mut("extensions").unwrap().set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("extensions").unwrap().add_error(err);
+            if severity(c) == Severity::Off || !c.applies(&ctx) {
+                continue;
+            }
+            let mut errors =
+                ErrorList::new(c.name(), self.options.error_limit(), baseline.as_ref());
+            cr.collect(&ctx, &mut errors);
+            set_check_result(self, &mut vsum, c.name(), errors, cascading);
+            //-- the validation stops after errors in the schema or the Extensions
+            if check::STOPPING_CHECKS.contains(&c.name()) && has_errors(&vsum) {
+                if !exhaustive {
+                    return vsum;
                 }
-                return vsum;
+                cascading = true;
             }
         }
+        vsum
+    }
 
-        //-- parents_children_consistency
-        re = self.parents_children_consistency();
-        match re {
-            Ok(_) => vsum
-                .get_mut("parents_children_consistency")
-                .unwrap()
-                .set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("parents_children_consistency")
-                        .unwrap()
-                        .add_error(err);
-                }
-            }
-        }
-        //-- wrong_vertex_index
-        re = self.wrong_vertex_index();
-        match re {
-            Ok(_) => vsum
-                .get_mut("wrong_vertex_index")
-                .unwrap()
-                .set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("wrong_vertex_index").unwrap().add_error(err);
-                }
-            }
+    /// The location in the input of the value of a diagnostic (its JSON
+    /// Pointer, or else its CityObject); for a CityJSONFeature of a
+    /// CityJSONSeq the line is that of the feature in the stream
+    fn locate(&self, d: &Diagnostic) -> Option<Location> {
+        let p = match (&d.pointer, &d.cityobject) {
+            (Some(p), _) => p.clone(),
+            (None, Some(id)) => format!("/CityObjects/{}", parse::escape(id)),
+            _ => return None,
+        };
+        let mut l = self.locations.locate(&p)?;
+        if let Some(line) = self.feature_line {
+            l.line = line;
         }
-        //-- semantics_arrays
-        re = self.semantics_arrays();
-        match re {
-            Ok(_) => vsum.get_mut("semantics_arrays").unwrap().set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("semantics_arrays").unwrap().add_error(err);
+        Some(l)
+    }
+
+    /// Returns the Extensions declared in "extensions" (sorted by name)
+    pub fn get_extensions(&self) -> Vec<ExtensionRef> {
+        ValidationContext::new(self).declared_extensions()
+    }
+
+    /// Resolves with the resolver all the Extensions declared in "extensions"
+    /// and adds them to the validator, returns the status of each one.
+    /// The other Extensions their `"$ref"` point to are also resolved (their
+    /// status has the URL as key).
+    /// ```rust
+    /// use cjval::{CJValidator, LocalDirResolver};
+    /// let sdata = std::fs::read_to_string("./data/noise1.city.json").unwrap();
+    /// let mut val = CJValidator::from_str(&sdata);
+    /// let statuses = val.resolve_extensions(&LocalDirResolver::new("./schemas/extensions/11"));
+    /// for (name, s) in &statuses {
+    ///     println!("{}: {:?}", name, s.error);
+    /// }
+    /// ```
+    pub fn resolve_extensions(
+        &mut self,
+        resolver: &dyn ExtensionResolver,
+    ) -> IndexMap<String, ExtensionStatus> {
+        let mut re: IndexMap<String, ExtensionStatus> = IndexMap::new();
+        for ext in self.get_extensions() {
+            re.insert(ext.name.clone(), self.resolve_one_extension(resolver, ext));
+        }
+        //-- the Extensions referenced by the "$ref" of those (the key is then the URL)
+        loop {
+            let mut missing: Vec<String> = Vec::new();
+            for jext in &self.jexts {
+                for r in schemaset::external_refs(jext, self.version_file) {
+                    let url = match extension_url(jext) {
+                        "" => r,
+                        base => schemaset::join_url(base, &r),
+                    };
+                    if self.find_extension(&url).is_err()
+                        && !re.contains_key(&url)
+                        && !missing.contains(&url)
+                    {
+                        missing.push(url);
+                    }
                 }
             }
-        }
-        //-- textures
-        re = self.textures();
-        match re {
-            Ok(_) => vsum.get_mut("textures").unwrap().set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("textures").unwrap().add_error(err);
-                }
+            if missing.is_empty() {
+                break;
             }
-        }
-        //-- materials
-        re = self.materials();
-        match re {
-            Ok(_) => vsum.get_mut("materials").unwrap().set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("materials").unwrap().add_error(err);
-                }
+            for url in missing {
+                let ext = ExtensionRef {
+                    name: schemaset::file_name(&url)
+                        .trim_end_matches(".ext.json")
+                        .to_string(),
+                    url: url.clone(),
+                    version: String::new(),
+                };
+                re.insert(url, self.resolve_one_extension(resolver, ext));
             }
         }
+        re
+    }
 
-        //-- warnings : only do if no errors so far
-        for (_c, summ) in vsum.iter() {
-            if summ.has_errors() == true {
-                return vsum;
-            }
-        }
-        //-- extra_root_properties
-        re = self.extra_root_properties();
-        match re {
-            Ok(_) => vsum
-                .get_mut("extra_root_properties")
-                .unwrap()
-                .set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("extra_root_properties")
-                        .unwrap()
-                        .add_error(err);
-                }
-            }
-        }
-        //-- duplicate_vertices
-        re = self.duplicate_vertices();
-        match re {
-            Ok(_) => vsum
-                .get_mut("duplicate_vertices")
-                .unwrap()
-                .set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("duplicate_vertices").unwrap().add_error(err);
-                }
-            }
-        }
-        //-- unused_vertices
-        re = self.unused_vertices();
-        match re {
-            Ok(_) => vsum.get_mut("unused_vertices").unwrap().set_validity(true),
-            Err(errs) => {
-                for err in errs {
-                    vsum.get_mut("unused_vertices").unwrap().add_error(err);
+    fn resolve_one_extension(
+        &mut self,
+        resolver: &dyn ExtensionResolver,
+        ext: ExtensionRef,
+    ) -> ExtensionStatus {
+        let mut ext_version: Option<String> = None;
+        let error = match resolver.resolve(&ext) {
+            Ok(s) => match self.add_one_extension_from_str(&s) {
+                Ok(_) => {
+                    ext_version = self.jexts.last().unwrap()["version"]
+                        .as_str()
+                        .map(|v| v.to_string());
+                    None
                 }
-            }
+                Err(e) => Some(e),
+            },
+            Err(e) => Some(e),
+        };
+        ExtensionStatus {
+            url: ext.url,
+            version: ext.version,
+            ext_version,
+            error: error.map(|e| e.to_string()),
         }
-        return vsum;
     }
 
     pub fn get_extensions_urls(&self) -> Option<HashMap<String, String>> {
         let mut re: HashMap<String, String> = HashMap::new();
-        let v = self.j.as_object().unwrap();
-        if v.contains_key("extensions") {
-            let exts = self.j.get("extensions").unwrap().as_object().unwrap();
+        let jcj = if self.is_cjfeature {
+            &self.jheader
+        } else {
+            &self.j
+        };
+        if let Some(exts) = jcj.get("extensions").and_then(|x| x.as_object()) {
             for key in exts.keys() {
                 re.insert(
                     key.to_string(),
-                    exts[key]["url"].as_str().unwrap().to_string(),
+                    exts[key]["url"].as_str().unwrap_or("").to_string(),
                 );
             }
         }
@@ -602,8 +927,10 @@ impl CJValidator {
     }
 
     pub fn is_empty_cityjson(&self) -> bool {
-        if (self.j["CityObjects"].as_object().unwrap().is_empty())
-            && (self.j["vertices"].as_array().unwrap().is_empty())
+        if self.j["CityObjects"]
+            .as_object()
+            .map_or(true, |x| x.is_empty())
+            && self.j["vertices"].as_array().map_or(true, |x| x.is_empty())
         {
             return true;
         }
@@ -621,13 +948,12 @@ impl CJValidator {
     pub fn get_cjseq_feature_id(&self) -> String {
         if self.is_cjfeature {
             match self.j.get("id") {
-                Some(x) => {
-                    return x.as_str().unwrap().to_string();
-                }
-                None => return "".to_string(),
+                Some(Value::String(x)) => x.to_string(),
+                Some(x) => x.to_string(),
+                None => "".to_string(),
             }
         } else {
-            return "".to_string();
+            "".to_string()
         }
     }
 
@@ -635,13 +961,23 @@ impl CJValidator {
         self.version_schema.to_owned()
     }
 
+    /// Returns the property of the CityJSONFeature, or if it's not there the
+    /// one of the CityJSON object (the 1st line of a CityJSONSeq).
+    fn get_with_header(&self, property: &str) -> &Value {
+        ValidationContext::new(self).root_property(property)
+    }
+
+    /// Returns (scale, translate) of the "transform", or the identity
+    /// if there is none.
+    fn get_transform(&self) -> ([f64; 3], [f64; 3]) {
+        ValidationContext::new(self).transform()
+    }
 
-    fn schema(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn schema(&self, ls_errors: &mut ErrorList) {
         //-- if type == CityJSON
         if self.is_cityjson == false {
-            let s: String = format!("Not a CityJSON file");
-            return Err(vec![s]);
+            ls_errors.push("Not a CityJSON file".to_string());
+            return;
         }
         if self.is_cjfeature == false {
             //-- which cityjson version
@@ -650,161 +986,198 @@ impl CJValidator {
                     "CityJSON version {} not supported (or missing) [only \"1.0\", \"1.1\", \"2.0\"]",
                     self.j["version"]
                 );
-                return Err(vec![s]);
+                ls_errors.push(s);
+                return;
             }
         }
 
-        if self.is_cjfeature == false {
-            let compiled = JSONSchema::options()
-                .with_draft(Draft::Draft7)
-                .compile(&self.jschema_cj)
-                .expect("A valid schema");
-            let result = compiled.validate(&self.j);
-            if let Err(errors) = result {
-                for error in errors {
-                    let s: String = format!("{} [path:{}]", error, error.instance_path);
-                    ls_errors.push(s);
-                }
-            }
+        let compiled = if self.is_cjfeature {
+            self.schemas.cityjsonfeature(self.version_file)
         } else {
-            let compiled = JSONSchema::options()
-                .with_draft(Draft::Draft7)
-                .compile(&self.jschema_cjf)
-                .expect("A valid schema");
+            self.schemas.cityjson(self.version_file)
+        };
+        if let Some(compiled) = compiled {
             let result = compiled.validate(&self.j);
             if let Err(errors) = result {
                 for error in errors {
-                    let s: String = format!("{} [path:{}]", error, error.instance_path);
-                    ls_errors.push(s);
+                    let path = error.instance_path.to_string();
+                    let s: String = format!("{} [path:{}]", error, path);
+                    if path.is_empty() {
+                        ls_errors.push(s);
+                    } else {
+                        ls_errors.push_at(&path, s);
+                    }
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
+        if self.duplicate_keys {
+            ls_errors.push("Duplicate keys in 'CityObjects'".to_string());
+        }
+    }
+
+    /// Returns the Extension that defines each CityObject type, root property,
+    /// attribute ("{CityObject type}/{attribute}") and semantic surface
+    /// of the Extensions added
+    /// ```rust
+    /// use cjval::CJValidator;
+    /// let sdata = std::fs::read_to_string("./data/noise1.city.json").unwrap();
+    /// let sext = std::fs::read_to_string("./schemas/extensions/11/noise.ext.json").unwrap();
+    /// let mut val = CJValidator::from_str(&sdata);
+    /// let _ = val.add_one_extension_from_str(&sext);
+    /// let defs = val.get_extension_definitions();
+    /// assert_eq!(defs["+NoiseCityFurnitureSegment"], "Noise");
+    /// ```
+    pub fn get_extension_definitions(&self) -> IndexMap<String, String> {
+        let mut re: IndexMap<String, String> = IndexMap::new();
+        for jext in &self.jexts {
+            let name = jext["name"].as_str().unwrap_or("unnamed");
+            for (_kind, key, _schema) in extension_definitions(jext) {
+                re.entry(key).or_insert(name.to_string());
+            }
+        }
+        re
+    }
+
+    fn validate_ext_conflicts(&self, push: &mut impl FnMut(Diagnostic)) {
+        let mut defs: HashMap<(&str, String), (&str, &Value)> = HashMap::new();
+        for jext in &self.jexts {
+            let name = jext["name"].as_str().unwrap_or("unnamed");
+            for (kind, key, schema) in extension_definitions(jext) {
+                match defs.get(&(kind, key.clone())) {
+                    Some((other, s2)) => {
+                        if *s2 != schema {
+                            push(Diagnostic::new(&format!(
+                                "'{}' ({}) is defined differently by the Extensions '{}' and '{}'",
+                                key, kind, other, name
+                            )));
+                        }
+                    }
+                    None => {
+                        defs.insert((kind, key), (name, schema));
+                    }
+                }
+            }
         }
     }
 
-    fn validate_ext_extracityobjects(&self, jext: &Value) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_ext_extracityobjects(&self, jext: &Value, push: &mut impl FnMut(Diagnostic)) {
         //-- 1. build the schema file from the Extension file
-        let v = jext.get("extraCityObjects").unwrap().as_object().unwrap();
-        let jexto = jext.as_object().unwrap();
+        let Some(v) = jext["extraCityObjects"].as_object() else {
+            return;
+        };
         for eco in v.keys() {
             // println!("==>{:?}", eco);
-            let mut schema = jext["extraCityObjects"][eco].clone();
-            schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
-            if self.version_file == 11 {
-                schema["$id"] = json!("https://www.cityjson.org/schemas/1.1.0/tmp.json");
-            } else if self.version_file == 20 {
-                schema["$id"] = json!("https://www.cityjson.org/schemas/2.0.0/tmp.json");
-            }
-            for each in jexto.keys() {
-                let ss = each.as_str();
-                if EXTENSION_FIXED_NAMES.contains(&ss) == false {
-                    schema[ss] = jext[ss].clone();
+            let compiled = match self.get_compiled_schema_extension(
+                jext,
+                &format!("/extraCityObjects/{}", eco),
+                || self.assemble_extension_schema(jext, &jext["extraCityObjects"][eco]),
+            ) {
+                Ok(c) => c,
+                Err(e) => {
+                    push(Diagnostic::new(&format!(
+                        "The schema of '{}' cannot be used: {}",
+                        eco, e
+                    )));
+                    continue;
                 }
-            }
-            // println!("=>{}", serde_json::to_string(&schema).unwrap());
-            let compiled = self.get_compiled_schema_extension(&schema).unwrap();
+            };
             //-- 2. fetch the CO
-            let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+            let Some(cos) = self.j["CityObjects"].as_object() else {
+                return;
+            };
             for co in cos.keys() {
-                let tmp = cos.get(co).unwrap().as_object().unwrap();
-                if tmp["type"].as_str().unwrap() == eco {
+                if cos[co]["type"] == eco.as_str() {
                     // println!("here");
                     let result = compiled.validate(&self.j["CityObjects"][co]);
                     if let Err(errors) = result {
                         for error in errors {
                             let s: String = format!("{} [path:{}]", error, error.instance_path);
-                            ls_errors.push(s);
+                            let p = format!(
+                                "/CityObjects/{}{}",
+                                parse::escape(co),
+                                error.instance_path
+                            );
+                            push(Diagnostic::at(&p, &s));
                         }
                     }
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn validate_ext_extrarootproperties(&self, jext: &Value) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_ext_extrarootproperties(&self, jext: &Value, push: &mut impl FnMut(Diagnostic)) {
         //-- 1. build the schema file from the Extension file
-        let v = jext
-            .get("extraRootProperties")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let jexto = jext.as_object().unwrap();
+        let Some(v) = jext["extraRootProperties"].as_object() else {
+            return;
+        };
         for rp in v.keys() {
             // println!("==>{:?}", eco);
-            let mut schema = jext["extraRootProperties"][rp].clone();
-            schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
-            if self.version_file == 11 {
-                schema["$id"] = json!("https://www.cityjson.org/schemas/1.1.0/tmp.json");
-            } else if self.version_file == 20 {
-                schema["$id"] = json!("https://www.cityjson.org/schemas/2.0.0/tmp.json");
-            }
-            for each in jexto.keys() {
-                let ss = each.as_str();
-                if EXTENSION_FIXED_NAMES.contains(&ss) == false {
-                    schema[ss] = jext[ss].clone();
+            let compiled = match self.get_compiled_schema_extension(
+                jext,
+                &format!("/extraRootProperties/{}", rp),
+                || self.assemble_extension_schema(jext, &jext["extraRootProperties"][rp]),
+            ) {
+                Ok(c) => c,
+                Err(e) => {
+                    push(Diagnostic::new(&format!(
+                        "The schema of '{}' cannot be used: {}",
+                        rp, e
+                    )));
+                    continue;
                 }
-            }
-            let compiled = self.get_compiled_schema_extension(&schema).unwrap();
+            };
 
-            for k in self.j.as_object().unwrap().keys() {
+            for k in self.j.as_object().into_iter().flat_map(|t| t.keys()) {
                 if k == rp {
                     let result = compiled.validate(&self.j[k]);
                     if let Err(errors) = result {
                         for error in errors {
                             let s: String = format!("{} [path:{}]", error, error.instance_path);
-                            ls_errors.push(s);
+                            let p = format!("/{}{}", parse::escape(k), error.instance_path);
+                            push(Diagnostic::at(&p, &s));
                         }
                     }
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn validate_ext_extraattributes(&self, jext: &Value) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_ext_extraattributes(&self, jext: &Value, push: &mut impl FnMut(Diagnostic)) {
         //-- 1. build the schema file from the Extension file
-        let v = jext.get("extraAttributes").unwrap().as_object().unwrap();
-        let jexto = jext.as_object().unwrap();
+        let Some(v) = jext["extraAttributes"].as_object() else {
+            return;
+        };
         for cotype in v.keys() {
             //-- for each CityObject type
-            for eatt in jext["extraAttributes"][cotype].as_object().unwrap().keys() {
-                let mut schema = jext["extraAttributes"][cotype][eatt.as_str()].clone();
-                schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
-                if self.version_file == 11 {
-                    schema["$id"] = json!("https://www.cityjson.org/schemas/1.1.0/tmp.json");
-                } else if self.version_file == 20 {
-                    schema["$id"] = json!("https://www.cityjson.org/schemas/2.0.0/tmp.json");
-                }
-                for each in jexto.keys() {
-                    let ss = each.as_str();
-                    if EXTENSION_FIXED_NAMES.contains(&ss) == false {
-                        schema[ss] = jext[ss].clone();
+            let Some(eatts) = jext["extraAttributes"][cotype].as_object() else {
+                continue;
+            };
+            for eatt in eatts.keys() {
+                let compiled = match self.get_compiled_schema_extension(
+                    jext,
+                    &format!("/extraAttributes/{}/{}", cotype, eatt),
+                    || {
+                        self.assemble_extension_schema(
+                            jext,
+                            &jext["extraAttributes"][cotype][eatt.as_str()],
+                        )
+                    },
+                ) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        push(Diagnostic::new(&format!(
+                            "The schema of '{}' cannot be used: {}",
+                            eatt, e
+                        )));
+                        continue;
                     }
-                }
-                let compiled = self.get_compiled_schema_extension(&schema).unwrap();
-                let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+                };
+                let Some(cos) = self.j["CityObjects"].as_object() else {
+                    return;
+                };
                 for oneco in cos.keys() {
-                    let tmp = cos.get(oneco).unwrap().as_object().unwrap();
-                    if tmp["type"].as_str().unwrap() == cotype
-                        && tmp.contains_key("attributes")
-                        && tmp["attributes"].as_object().unwrap().contains_key(eatt)
-                    {
+                    let tmp = &cos[oneco];
+                    if tmp["type"] == cotype.as_str() && tmp["attributes"].get(eatt).is_some() {
                         let result =
                             compiled.validate(&self.j["CityObjects"][oneco]["attributes"][eatt]);
                         if let Err(errors) = result {
@@ -813,26 +1186,58 @@ impl CJValidator {
                                     "/CityObjects/{}/{} -- {} [path:{}]",
                                     oneco, eatt, error, error.instance_path
                                 );
-                                ls_errors.push(s);
+                                let p = format!(
+                                    "/CityObjects/{}/attributes/{}{}",
+                                    parse::escape(oneco),
+                                    parse::escape(eatt),
+                                    error.instance_path
+                                );
+                                push(Diagnostic::at(&p, &s));
                             }
                         }
                     }
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
+    }
+
+    /// The "attributes" of the CityObjects validated against the schemas given
+    /// with [`CJValidator::add_attribute_schema`]
+    fn attribute_schemas(&self, ls_errors: &mut ErrorList) {
+        let Some(cos) = self.j["CityObjects"].as_object() else {
+            return;
+        };
+        for (cotype, compiled) in &self.attribute_schemas {
+            let empty = json!({});
+            for (id, co) in cos {
+                if co["type"] != cotype.as_str() {
+                    continue;
+                }
+                let atts = co.get("attributes").unwrap_or(&empty);
+                if let Err(errors) = compiled.validate(atts) {
+                    for error in errors {
+                        let path = error.instance_path.to_string();
+                        //-- the attribute is the 1st segment of the path
+                        let s = match path.split('/').nth(1) {
+                            Some(att) => format!(
+                                "/CityObjects/{}/attributes/{} -- {} [path:{}]",
+                                id, att, error, path
+                            ),
+                            None => format!("/CityObjects/{}/attributes -- {}", id, error),
+                        };
+                        let p = format!("/CityObjects/{}/attributes{}", parse::escape(id), path);
+                        ls_errors.push_at(&p, s);
+                    }
+                }
+            }
         }
     }
 
-    fn validate_ext_extrasemanticsurfaces(&self, jext: &Value) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_ext_extrasemanticsurfaces(&self, jext: &Value, push: &mut impl FnMut(Diagnostic)) {
         //-- 0. check if "extraSemanticSurfaces" is in the file, if not then all good
         let t = jext["extraSemanticSurfaces"].as_object();
         if t.is_none() {
-            return Ok(());
+            return;
         }
         //-- 1. build the schema file from the Extension file
         let v = jext
@@ -840,23 +1245,24 @@ impl CJValidator {
             .unwrap()
             .as_object()
             .unwrap();
-        let jexto = jext.as_object().unwrap();
         for semsurf in v.keys() {
-            let mut schema = jext["extraSemanticSurfaces"][semsurf].clone();
-            schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
-            if self.version_file == 11 {
-                schema["$id"] = json!("https://www.cityjson.org/schemas/1.1.0/tmp.json");
-            } else if self.version_file == 20 {
-                schema["$id"] = json!("https://www.cityjson.org/schemas/2.0.0/tmp.json");
-            }
-            for each in jexto.keys() {
-                let ss = each.as_str();
-                if EXTENSION_FIXED_NAMES.contains(&ss) == false {
-                    schema[ss] = jext[ss].clone();
+            let compiled = match self.get_compiled_schema_extension(
+                jext,
+                &format!("/extraSemanticSurfaces/{}", semsurf),
+                || self.assemble_extension_schema(jext, &jext["extraSemanticSurfaces"][semsurf]),
+            ) {
+                Ok(c) => c,
+                Err(e) => {
+                    push(Diagnostic::new(&format!(
+                        "The schema of '{}' cannot be used: {}",
+                        semsurf, e
+                    )));
+                    continue;
                 }
-            }
-            let compiled = self.get_compiled_schema_extension(&schema).unwrap();
-            let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+            };
+            let Some(cos) = self.j["CityObjects"].as_object() else {
+                return;
+            };
             for key in cos.keys() {
                 //-- check geometry
                 let x = self.j["CityObjects"][key]["geometry"].as_array();
@@ -865,8 +1271,7 @@ impl CJValidator {
                         let surfs = g["semantics"]["surfaces"].as_array();
                         if surfs.is_some() {
                             for (j, surf) in surfs.unwrap().iter().enumerate() {
-                                let tmp = surf.as_object().unwrap();
-                                if tmp["type"].as_str().unwrap() == semsurf {
+                                if surf["type"] == semsurf.as_str() {
                                     let result = compiled.validate(
                                         &self.j["CityObjects"][key]["geometry"][i]["semantics"]
                                             ["surfaces"][j],
@@ -877,7 +1282,14 @@ impl CJValidator {
                                                 "/CityObjects/{} -- {} [path:{}]",
                                                 key, error, error.instance_path
                                             );
-                                            ls_errors.push(s);
+                                            let p = format!(
+                                                "/CityObjects/{}/geometry/{}/semantics/surfaces/{}{}",
+                                                parse::escape(key),
+                                                i,
+                                                j,
+                                                error.instance_path
+                                            );
+                                            push(Diagnostic::at(&p, &s));
                                         }
                                     }
                                 }
@@ -887,173 +1299,191 @@ impl CJValidator {
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
-    }
-
-    fn get_compiled_schema_extension(&self, schema: &Value) -> Option<JSONSchema> {
-        if self.version_file == 11 {
-            let s_1 = include_str!("../schemas/11/cityobjects.schema.json");
-            let s_2 = include_str!("../schemas/11/geomprimitives.schema.json");
-            let s_3 = include_str!("../schemas/11/appearance.schema.json");
-            let s_4 = include_str!("../schemas/11/geomtemplates.schema.json");
-            let schema_1 = serde_json::from_str(s_1).unwrap();
-            let schema_2 = serde_json::from_str(s_2).unwrap();
-            let schema_3 = serde_json::from_str(s_3).unwrap();
-            let schema_4 = serde_json::from_str(s_4).unwrap();
-            let compiled = JSONSchema::options()
-                .with_draft(Draft::Draft7)
-                .with_document(
-                    "https://www.cityjson.org/schemas/1.1.0/cityobjects.schema.json".to_string(),
-                    schema_1,
-                )
-                .with_document(
-                    "https://www.cityjson.org/schemas/1.1.0/geomprimitives.schema.json".to_string(),
-                    schema_2,
-                )
-                .with_document(
-                    "https://www.cityjson.org/schemas/1.1.0/appearance.schema.json".to_string(),
-                    schema_3,
-                )
-                .with_document(
-                    "https://www.cityjson.org/schemas/1.1.0/geomtemplates.schema.json".to_string(),
-                    schema_4,
-                )
-                .compile(&schema)
-                .expect("A valid schema");
-            return Some(compiled);
-        } else if self.version_file == 20 {
-            let s_1 = include_str!("../schemas/20/cityobjects.schema.json");
-            let s_2 = include_str!("../schemas/20/geomprimitives.schema.json");
-            let s_3 = include_str!("../schemas/20/appearance.schema.json");
-            let s_4 = include_str!("../schemas/20/geomtemplates.schema.json");
-            let schema_1 = serde_json::from_str(s_1).unwrap();
-            let schema_2 = serde_json::from_str(s_2).unwrap();
-            let schema_3 = serde_json::from_str(s_3).unwrap();
-            let schema_4 = serde_json::from_str(s_4).unwrap();
-            let compiled = JSONSchema::options()
-                .with_draft(Draft::Draft7)
-                .with_document(
-                    "https://www.cityjson.org/schemas/2.0.0/cityobjects.schema.json".to_string(),
-                    schema_1,
-                )
-                .with_document(
-                    "https://www.cityjson.org/schemas/2.0.0/geomprimitives.schema.json".to_string(),
-                    schema_2,
-                )
-                .with_document(
-                    "https://www.cityjson.org/schemas/2.0.0/appearance.schema.json".to_string(),
-                    schema_3,
-                )
-                .with_document(
-                    "https://www.cityjson.org/schemas/2.0.0/geomtemplates.schema.json".to_string(),
-                    schema_4,
-                )
-                .compile(&schema)
-                .expect("A valid schema");
-            return Some(compiled);
-        } else {
-            return None;
+    }
+
+    /// The schema of a property of the Extension, with its "definitions" (and
+    /// the other properties that are not fixed names)
+    fn assemble_extension_schema(&self, jext: &Value, property: &Value) -> Value {
+        let mut schema = schemaset::object_schema(property);
+        schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+        if let Some(id) = schemaset::extension_schema_id(self.version_file) {
+            schema["$id"] = json!(id);
+        }
+        for (k, v) in jext.as_object().into_iter().flatten() {
+            if !EXTENSION_FIXED_NAMES.contains(&k.as_str()) {
+                schema[k] = v.clone();
+            }
+        }
+        schema
+    }
+
+    /// Compiles the schema of the property of the Extension at `pointer` (eg
+    /// "/extraCityObjects/+NoiseCityFurnitureSegment") with the other
+    /// Extensions it references, those must have been added to the validator.
+    /// The schema is assembled and compiled only if it is not in the cache of
+    /// the SchemaSet (the key is computed from the Extensions loaded).
+    fn get_compiled_schema_extension(
+        &self,
+        jext: &Value,
+        pointer: &str,
+        assemble: impl FnOnce() -> Value,
+    ) -> Result<Arc<JSONSchema>, String> {
+        let iext = self.jexts.iter().position(|e| std::ptr::eq(e, jext));
+        let mut h = DefaultHasher::new();
+        (self.version_file, self.exts_key, iext, pointer).hash(&mut h);
+        self.schemas.extension(h.finish(), || {
+            let schema = assemble();
+            let id = schema["$id"].as_str().unwrap_or("");
+            let mut docs: Vec<(String, Value)> = Vec::new();
+            for r in schemaset::external_refs(&schema, self.version_file) {
+                let other = match extension_url(jext) {
+                    "" => self.find_extension(&r)?,
+                    base => self.find_extension(&schemaset::join_url(base, &r))?,
+                };
+                //-- its own "$ref" to the CityJSON schemas are resolved like ours
+                let mut doc = other.clone();
+                doc["$id"] = json!(schemaset::join_url(id, schemaset::file_name(&r)));
+                docs.push((schemaset::join_url(id, &r), doc));
+            }
+            schemaset::try_compile_extension(self.version_file, &schema, &docs)
+                .ok_or(format!("CityJSON v{} has no Extensions", self.version_file))?
+        })
+    }
+
+    /// The loaded Extension at a URL, or else the only one with the same file
+    /// name (the URL in the Extension file is often not where it is served)
+    fn find_extension(&self, url: &str) -> Result<&Value, String> {
+        if let Some(jext) = self.jexts.iter().find(|jext| extension_url(jext) == url) {
+            return Ok(jext);
+        }
+        let fname = schemaset::file_name(url);
+        let mut candidates = self.jexts.iter().filter(|jext| {
+            let u = extension_url(jext);
+            !u.is_empty() && schemaset::file_name(u) == fname
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(jext), None) => Ok(jext),
+            (None, _) => Err(format!(
+                "\"{}\" cannot be resolved, the Extension is not loaded",
+                url
+            )),
+            (Some(_), Some(_)) => Err(format!(
+                "\"{}\" cannot be resolved, several Extensions loaded are named \"{}\"",
+                url, fname
+            )),
+        }
+    }
+
+    /// The version declared in "extensions" must be the one of the Extension
+    /// file (the Extension file is found with its "name")
+    fn extension_versions(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        for ext in ctx.declared_extensions() {
+            let Some(jext) = ctx.extensions().iter().find(|e| e["name"] == ext.name) else {
+                continue;
+            };
+            let actual = jext["version"].as_str().unwrap_or("");
+            if !ext.matches_version(actual) {
+                ls_errors.push(format!(
+                    "Extension '{}' is declared with version {} but the Extension file has version {}",
+                    ext.name, ext.version, actual
+                ));
+            }
         }
     }
 
-    fn validate_extensions(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_extensions(&self, ls_errors: &mut ErrorList) {
+        //-- for a CityJSONFeature, the errors say which feature
+        let loc = if self.is_cjfeature {
+            format!(" {}", ValidationContext::new(self).feature_location())
+        } else {
+            String::new()
+        };
+        let mut push = |d: Diagnostic| {
+            ls_errors.push_diagnostic(Diagnostic {
+                message: format!("{}{}", d.message, loc),
+                ..d
+            })
+        };
+        //-- 0. the same +names defined differently by 2 Extensions
+        self.validate_ext_conflicts(&mut push);
         for ext in &self.jexts {
             //-- 0. check the version of CityJSON
             let mut v: String = self.version_file.to_string();
             v.insert(1, '.');
             if let Some(e) = ext.as_object() {
-                if e["type"] != "CityJSONExtension" {
-                    let s: String = format!("Extension is old (v1.0) or invalid.");
-                    ls_errors.push(s);
+                //-- v1.0 Extensions have no "type" and no "versionCityJSON"
+                if self.version_file == 10 {
+                    if e.contains_key("versionCityJSON") {
+                        push(Diagnostic::new(&format!(
+                            "Extension 'versionCityJSON' != CityJSON version of file [{} != 1.0]",
+                            e["versionCityJSON"]
+                        )));
+                        continue;
+                    }
+                } else if e["type"] != "CityJSONExtension" {
+                    push(Diagnostic::new("Extension is old (v1.0) or invalid."));
                     continue;
-                }
-                if e.contains_key("versionCityJSON") == false {
+                } else if e.contains_key("versionCityJSON") == false {
                     let s: String = format!(
                         "Extension is too old and not for v1.1+, or doesn't contain the key 'versionCityJSON'");
-                    ls_errors.push(s);
+                    push(Diagnostic::new(&s));
                     continue;
                 }
             }
-            if ext["versionCityJSON"] != v {
+            if self.version_file != 10 && ext["versionCityJSON"] != v {
                 let s: String = format!(
                     "Extension 'versionCityJSON' != CityJSON version of file [{} != {}]",
-                    ext["versionCityJSON"].as_str().unwrap(),
+                    ext["versionCityJSON"]
+                        .as_str()
+                        .map_or_else(|| ext["versionCityJSON"].to_string(), |x| x.to_string()),
                     v
                 );
-                ls_errors.push(s);
-            }
+                push(Diagnostic::new(&s));
+            }
+            //-- the errors are attributed to the Extension
+            let name = ext["name"].as_str().unwrap_or("unnamed");
+            let mut push_ext = |d: Diagnostic| {
+                push(Diagnostic {
+                    message: format!("{} [Extension:{}]", d.message, name),
+                    ..d
+                })
+            };
             //-- 1. extraCityObjects
-            let mut re = self.validate_ext_extracityobjects(&ext);
-            if re.is_err() {
-                ls_errors.append(&mut re.err().unwrap());
-            }
+            self.validate_ext_extracityobjects(ext, &mut push_ext);
             //-- 2. extraRootProperties
-            re = self.validate_ext_extrarootproperties(&ext);
-            if re.is_err() {
-                ls_errors.append(&mut re.err().unwrap());
-            }
+            self.validate_ext_extrarootproperties(ext, &mut push_ext);
             //-- 3. extraAttributes
-            re = self.validate_ext_extraattributes(&ext);
-            if re.is_err() {
-                ls_errors.append(&mut re.err().unwrap());
-            }
+            self.validate_ext_extraattributes(ext, &mut push_ext);
             if self.version_file >= 20 {
                 //-- 4. extraSemanticSurfaces
-                re = self.validate_ext_extrasemanticsurfaces(&ext);
-                if re.is_err() {
-                    ls_errors.append(&mut re.err().unwrap());
-                }
+                self.validate_ext_extrasemanticsurfaces(ext, &mut push_ext);
             }
         }
         //-- 5. check if there are CityObjects that do not have a schema
-        let mut re = self.validate_ext_co_without_schema();
-        if re.is_err() {
-            ls_errors.append(&mut re.err().unwrap());
-        }
+        self.validate_ext_co_without_schema(&mut push);
         //-- 6. check if there are extra root properties that do not have a schema
-        re = self.validate_ext_rootproperty_without_schema();
-        if re.is_err() {
-            ls_errors.append(&mut re.err().unwrap());
-        }
+        self.validate_ext_rootproperty_without_schema(&mut push);
         //-- 7. check for the extra attributes w/o schemas
-        re = self.validate_ext_attribute_without_schema();
-        if re.is_err() {
-            ls_errors.append(&mut re.err().unwrap());
-        }
+        self.validate_ext_attribute_without_schema(&mut push);
         //-- 8. check for the semsurfs w/o schemas
         if self.version_file >= 20 {
-            re = self.validate_ext_semsurf_without_schema();
-            if re.is_err() {
-                ls_errors.append(&mut re.err().unwrap());
-            }
-        }
-
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
+            self.validate_ext_semsurf_without_schema(&mut push);
         }
     }
 
-    fn validate_ext_semsurf_without_schema(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_ext_semsurf_without_schema(&self, push: &mut impl FnMut(Diagnostic)) {
         let mut newss: Vec<String> = Vec::new();
         for jext in &self.jexts {
-            let re = jext.get("extraSemanticSurfaces");
-            if re.is_some() {
-                let v = re.unwrap().as_object().unwrap();
+            if let Some(v) = jext["extraSemanticSurfaces"].as_object() {
                 for ess in v.keys() {
                     newss.push(ess.to_string());
                 }
             }
         }
         //-- fetch the COs
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+        let Some(cos) = self.j["CityObjects"].as_object() else {
+            return;
+        };
         for key in cos.keys() {
             let x = self.j["CityObjects"][key]["geometry"].as_array();
             if x.is_some() {
@@ -1061,8 +1491,7 @@ impl CJValidator {
                     let surfs = g["semantics"]["surfaces"].as_array();
                     if surfs.is_some() {
                         for surf in surfs.unwrap() {
-                            let tmp = surf.as_object().unwrap();
-                            let thetype = tmp["type"].as_str().unwrap().to_string();
+                            let thetype = surf["type"].as_str().unwrap_or("").to_string();
                             if thetype.chars().next() == Some('+')
                                 && newss.contains(&thetype) == false
                             {
@@ -1070,34 +1499,33 @@ impl CJValidator {
                                     "/CityObjects/{} -- Semantic Surface '{}' doesn't have a schema",
                                     key, thetype
                                 );
-                                ls_errors.push(s);
+                                push(Diagnostic::new(&s).with_cityobject(key));
                             }
                         }
                     }
                 }
             }
         }
-
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn validate_ext_attribute_without_schema(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_ext_attribute_without_schema(&self, push: &mut impl FnMut(Diagnostic)) {
         let mut ls_plusattrs: HashSet<String> = HashSet::new();
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+        let Some(cos) = self.j["CityObjects"].as_object() else {
+            return;
+        };
         for theid in cos.keys() {
-            let co = cos.get(theid).unwrap().as_object().unwrap();
+            let Some(co) = cos[theid].as_object() else {
+                continue;
+            };
             if co.contains_key("attributes") {
-                let attrs = co.get("attributes").unwrap().as_object().unwrap();
+                let Some(attrs) = co["attributes"].as_object() else {
+                    continue;
+                };
                 for attr in attrs.keys() {
                     let sattr = attr.as_str();
                     if sattr.chars().next() == Some('+') {
                         // println!("attr: {:?}", sattr);
-                        let a = format!("{}/{}", co.get("type").unwrap().as_str().unwrap(), sattr);
+                        let a = format!("{}/{}", co["type"].as_str().unwrap_or(""), sattr);
                         ls_plusattrs.insert(a);
                     }
                 }
@@ -1116,78 +1544,77 @@ impl CJValidator {
             }
             if !found {
                 let s: String = format!("Attribute '{}' doesn't have a schema", each);
-                ls_errors.push(s);
+                push(Diagnostic::new(&s));
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn validate_ext_co_without_schema(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_ext_co_without_schema(&self, push: &mut impl FnMut(Diagnostic)) {
         let mut newcos: Vec<String> = Vec::new();
         for jext in &self.jexts {
-            let v = jext.get("extraCityObjects").unwrap().as_object().unwrap();
-            for eco in v.keys() {
-                newcos.push(eco.to_string());
+            if let Some(v) = jext["extraCityObjects"].as_object() {
+                for eco in v.keys() {
+                    newcos.push(eco.to_string());
+                }
             }
         }
         //-- fetch the COs
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+        let Some(cos) = self.j["CityObjects"].as_object() else {
+            return;
+        };
         for co in cos.keys() {
-            let tmp = cos.get(co).unwrap().as_object().unwrap();
-            let thetype = tmp["type"].as_str().unwrap().to_string();
+            let thetype = cos[co]["type"].as_str().unwrap_or("").to_string();
             if thetype.chars().next() == Some('+') && newcos.contains(&thetype) == false {
                 let s: String = format!(
                     "/CityObjects/{} -- CityObject '{}' doesn't have a schema",
                     co, thetype
                 );
-                ls_errors.push(s);
+                push(Diagnostic::new(&s).with_cityobject(co));
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn validate_ext_rootproperty_without_schema(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
+    fn validate_ext_rootproperty_without_schema(&self, push: &mut impl FnMut(Diagnostic)) {
         let mut newrps: Vec<String> = Vec::new();
         for jext in &self.jexts {
-            let v = jext
-                .get("extraRootProperties")
-                .unwrap()
-                .as_object()
-                .unwrap();
-            for erp in v.keys() {
-                newrps.push(erp.to_string());
+            if let Some(v) = jext["extraRootProperties"].as_object() {
+                for erp in v.keys() {
+                    newrps.push(erp.to_string());
+                }
             }
         }
-        let t = self.j.as_object().unwrap();
+        let Some(t) = self.j.as_object() else {
+            return;
+        };
         for each in t.keys() {
             let s = each.to_string();
             if s.chars().next() == Some('+') && (newrps.contains(&s) == false) {
                 let s: String = format!("Extra root property '{}' doesn't have a schema", s);
-                ls_errors.push(s);
+                push(Diagnostic::new(&s));
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn extra_root_properties(&self) -> Result<(), Vec<String>> {
-        if self.is_cjfeature {
-            return Ok(());
+    fn extra_root_properties(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        let cj = ctx.cityjson();
+        if ctx.is_cjfeature() {
+            //-- v2.0 allows extra root properties in a CityJSONFeature, v1.1
+            //-- doesn't and they are already errors of the schema
+            if ctx.version() != "2.0" {
+                return;
+            }
+            let rootproperties: [&str; 5] = ["type", "id", "CityObjects", "vertices", "appearance"];
+            for each in cj.as_object().into_iter().flat_map(|t| t.keys()) {
+                if !each.starts_with('+') && !rootproperties.contains(&each.as_str()) {
+                    ls_errors.push(format!(
+                        "Root property '{}' of CityJSONFeature is not in CityJSON schema, might be ignored by some parsers {}",
+                        each,
+                        ctx.feature_location()
+                    ));
+                }
+            }
+            return;
         };
-        let mut ls_warnings: Vec<String> = Vec::new();
         let rootproperties: [&str; 9] = [
             "type",
             "version",
@@ -1199,62 +1626,47 @@ impl CJValidator {
             "appearance",
             "geometry-templates",
         ];
-        let t = self.j.as_object().unwrap();
+        let Some(t) = cj.as_object() else {
+            return;
+        };
         for each in t.keys() {
             let s = each.to_string();
             if s.chars().next() != Some('+') && (rootproperties.contains(&s.as_str()) == false) {
                 let s: String = format!("Root property '{}' is not in CityJSON schema, might be ignored by some parsers", s);
-                ls_warnings.push(s);
+                ls_errors.push(s);
             }
         }
-        if ls_warnings.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_warnings)
-        }
     }
 
     // parents_children_consistency
-    fn parents_children_consistency(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+    fn parents_children_consistency(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        let Some(cos) = ctx.cityjson()["CityObjects"].as_object() else {
+            return;
+        };
         //-- do children have the parent too?
         for key in cos.keys() {
-            let co = cos.get(key).unwrap().as_object().unwrap();
-            if co.contains_key("children") {
-                let thechildrenkeys = co.get("children").unwrap().as_array().unwrap();
-                for ckey in thechildrenkeys {
-                    if !cos.contains_key(ckey.as_str().unwrap()) {
+            let Some(thechildrenkeys) = cos[key]["children"].as_array() else {
+                continue;
+            };
+            for ckey in thechildrenkeys.iter().filter_map(|k| k.as_str()) {
+                match cos.get(ckey) {
+                    None => {
                         let s = format!(
                             "CityObject #{} doesn't exist (referenced by #{})",
-                            ckey.as_str().unwrap(),
-                            key
+                            ckey, key
                         );
-                        ls_errors.push(s);
-                    } else {
-                        if (!cos
-                            .get(ckey.as_str().unwrap())
-                            .unwrap()
-                            .as_object()
-                            .unwrap()
-                            .contains_key("parents"))
-                            || (!cos
-                                .get(ckey.as_str().unwrap())
-                                .unwrap()
-                                .as_object()
-                                .unwrap()
-                                .get("parents")
-                                .unwrap()
-                                .as_array()
-                                .unwrap()
-                                .contains(&json!(key)))
-                        {
+                        ls_errors.push_in(key, s);
+                    }
+                    Some(child) => {
+                        let has_parent = child["parents"]
+                            .as_array()
+                            .is_some_and(|ps| ps.contains(&json!(key)));
+                        if !has_parent {
                             let s = format!(
                                 "CityObject #{} doesn't reference correct parent (#{})",
-                                ckey.as_str().unwrap(),
-                                key
+                                ckey, key
                             );
-                            ls_errors.push(s);
+                            ls_errors.push_in(ckey, s);
                         }
                     }
                 }
@@ -1262,240 +1674,239 @@ impl CJValidator {
         }
         //-- are there orphans?
         for key in cos.keys() {
-            let co = cos.get(key).unwrap().as_object().unwrap();
-            if co.contains_key("parents") {
-                let theparentkeys = co.get("parents").unwrap().as_array().unwrap();
-                for pkey in theparentkeys {
-                    if !cos.contains_key(pkey.as_str().unwrap()) {
-                        let s = format!(
-                            "CityObject #{} is an orphan (parent #{} doesn't exist)",
-                            key,
-                            pkey.as_str().unwrap()
-                        );
-                        ls_errors.push(s);
-                    }
+            let Some(theparentkeys) = cos[key]["parents"].as_array() else {
+                continue;
+            };
+            for pkey in theparentkeys.iter().filter_map(|k| k.as_str()) {
+                if !cos.contains_key(pkey) {
+                    let s = format!(
+                        "CityObject #{} is an orphan (parent #{} doesn't exist)",
+                        key, pkey
+                    );
+                    ls_errors.push_in(key, s);
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn duplicate_vertices(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
-        let vs = self.j.get("vertices").unwrap().as_array().unwrap();
+    fn duplicate_vertices(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        let Some(vs) = ctx.cityjson()["vertices"].as_array() else {
+            return;
+        };
+        //-- compare the real-world coordinates (after the transform)
+        let (scale, translate) = ctx.transform();
         // use all vertices as keys in a hashmap
-        let mut uniques = HashSet::new();
-        for i in 0..vs.len() {
-            let v = vs[i].as_array().unwrap();
-            let s: String = format!(
-                "{}{}{}",
-                v[0].to_string(),
-                v[1].to_string(),
-                v[2].to_string()
-            );
-            if !uniques.contains(&s) {
-                uniques.insert(s);
-            } else {
-                ls_errors.push(format!("Vertex ({}, {}, {}) duplicated", v[0], v[1], v[2]));
-            }
-        }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
+        let mut uniques: HashSet<[u64; 3]> = HashSet::new();
+        //-- (index, coordinates) of the duplicates
+        let mut dups: Vec<(usize, [f64; 3])> = Vec::new();
+        for (i, v) in vs.iter().enumerate() {
+            let v: Vec<f64> = v
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|c| c.as_f64())
+                .collect();
+            if v.len() != 3 {
+                continue;
+            }
+            let mut k: [u64; 3] = [0; 3];
+            for i in 0..3 {
+                let c = v[i] * scale[i] + translate[i];
+                //-- +0.0 to have the same key for -0.0 and 0.0
+                k[i] = (c + 0.0).to_bits();
+            }
+            if !uniques.insert(k) {
+                dups.push((i, [v[0], v[1], v[2]]));
+            }
+        }
+        if dups.is_empty() {
+            return;
+        }
+        //-- the errors are attributed to the CityObjects using the duplicates
+        let mut users: HashMap<usize, Vec<&str>> = dups.iter().map(|(i, _)| (*i, vec![])).collect();
+        for (id, co) in ctx.cityobjects() {
+            let mut indices: HashSet<usize> = HashSet::new();
+            collect_indices_cityobject(co, &mut indices);
+            for i in indices {
+                if let Some(ids) = users.get_mut(&i) {
+                    ids.push(id);
+                }
+            }
+        }
+        for (i, v) in dups {
+            let s = format!("Vertex ({}, {}, {}) duplicated", v[0], v[1], v[2]);
+            match users[&i].as_slice() {
+                [] => ls_errors.push(s),
+                ids => {
+                    for id in ids {
+                        ls_errors.push_in(id, s.clone());
+                    }
+                }
+            }
         }
     }
 
-    fn materials(&self) -> Result<(), Vec<String>> {
-        let mut max_index: usize = 0;
-        let x = self.j["appearance"]["materials"].as_array();
-        if x.is_some() {
-            max_index = x.unwrap().len();
-        }
-        let mut ls_errors: Vec<String> = Vec::new();
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+    fn materials(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        let cj = ctx.cityjson();
+        let max_index = ctx.root_property("appearance")["materials"]
+            .as_array()
+            .map_or(0, Vec::len);
+        let Some(cos) = cj["CityObjects"].as_object() else {
+            return;
+        };
         for theid in cos.keys() {
             //-- check geometry
-            let x = self.j["CityObjects"][theid]["geometry"].as_array();
-            if x.is_some() {
-                let gs = x.unwrap();
-                let mut gi = 0;
-                for g in gs {
-                    if g.get("material").is_none() {
-                        continue;
-                    }
-                    if g["type"] == "MultiSurface" || g["type"] == "CompositeSurface" {
-                        let bs = g["boundaries"].as_array().unwrap().len();
-                        let gm = g["material"].as_object().unwrap();
-                        for m_name in gm.keys() {
-                            let gmv = g["material"][m_name]["values"].as_array();
-                            if gmv.is_some() {
-                                let x = gmv.unwrap();
-                                if x.len() != bs {
-                                    ls_errors.push(format!(
-                                        "Material \"values\" not same dimension as \"boundaries\"; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
+            let gs = cj["CityObjects"][theid]["geometry"].as_array();
+            for (gi, g) in gs.into_iter().flatten().enumerate() {
+                if g.get("material").is_none() {
+                    continue;
+                }
+                if g["type"] == "MultiSurface" || g["type"] == "CompositeSurface" {
+                    let bs = g["boundaries"].as_array().map_or(0, Vec::len);
+                    let gm = g["material"].as_object().into_iter().flatten();
+                    for (m_name, _) in gm {
+                        if let Some(x) = g["material"][m_name]["values"].as_array() {
+                            if x.len() != bs {
+                                ls_errors.push_in(theid, format!(
+                                    "Material \"values\" not same dimension as \"boundaries\"; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
+                                ));
+                            }
+                            for each in x {
+                                if each.as_u64().is_some_and(|x| x >= max_index as u64) {
+                                    ls_errors.push_in(theid, format!(
+                                        "Reference in material \"values\" overflows (max={}); #{} and geom-#{} / material-\"{}\"",
+                                        max_index as i64 - 1, theid, gi, m_name
                                     ));
                                 }
-                                for each in x {
-                                    if (each.as_u64().is_some())
-                                        && (each.as_u64().unwrap() > (max_index - 1) as u64)
-                                    {
-                                        ls_errors.push(format!(
+                            }
+                        } else if let Some(value) = g["material"][m_name]["value"].as_u64() {
+                            if value >= max_index as u64 {
+                                ls_errors.push_in(theid, format!(
+                                    "Material \"value\" overflow; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
+                                ));
+                            }
+                        }
+                    }
+                } else if g["type"] == "Solid" {
+                    //-- length of the sem-surfaces == # of surfaces
+                    let mut bs: Vec<usize> = Vec::new();
+                    let shells = g["boundaries"].as_array().into_iter().flatten();
+                    for shell in shells {
+                        bs.push(shell.as_array().map_or(0, Vec::len));
+                    }
+                    let gm = g["material"].as_object().into_iter().flatten();
+                    for (m_name, _) in gm {
+                        let mut vs: Vec<usize> = Vec::new();
+                        if let Some(x) = g["material"][m_name]["values"].as_array() {
+                            for each in x {
+                                let xa = each.as_array().map_or(&[][..], Vec::as_slice);
+                                vs.push(xa.len());
+                                for each2 in xa {
+                                    if each2.as_u64().is_some_and(|x| x >= max_index as u64) {
+                                        ls_errors.push_in(theid, format!(
                                             "Reference in material \"values\" overflows (max={}); #{} and geom-#{} / material-\"{}\"",
-                                            (max_index-1),theid, gi, m_name
-                                        ));
-                                    }
-                                }
-                            } else {
-                                let ifvalue = g["material"][m_name]["value"].as_u64();
-                                if ifvalue.is_some() {
-                                    if ifvalue.unwrap() > (max_index - 1) as u64 {
-                                        ls_errors.push(format!(
-                                        "Material \"value\" overflow; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
+                                            max_index as i64 - 1, theid, gi, m_name
                                         ));
                                     }
                                 }
                             }
                         }
-                    } else if g["type"] == "Solid" {
-                        //-- length of the sem-surfaces == # of surfaces
-                        let mut bs: Vec<usize> = Vec::new();
-                        let shells = g["boundaries"].as_array().unwrap();
-                        for shell in shells {
-                            bs.push(shell.as_array().unwrap().len());
+                        if let Some(value) = g["material"][m_name]["value"].as_u64() {
+                            if value >= max_index as u64 {
+                                ls_errors.push_in(theid, format!(
+                                "Material \"value\" overflow; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
+                            ));
+                            }
+                        } else {
+                            if bs.iter().eq(vs.iter()) == false {
+                                ls_errors.push_in(theid, format!(
+                                "Material \"values\" not same dimension as \"boundaries\"; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
+                            ));
+                            }
+                        }
+                    }
+                } else if g["type"] == "MultiSolid" || g["type"] == "CompositeSolid" {
+                    //-- length of the sem-surfaces == # of surfaces
+                    let mut bs: Vec<Vec<usize>> = Vec::new();
+                    let solids = g["boundaries"].as_array().into_iter().flatten();
+                    for solid in solids {
+                        let asolid = solid.as_array().into_iter().flatten();
+                        let mut tmp: Vec<usize> = Vec::new();
+                        for surface in asolid {
+                            tmp.push(surface.as_array().map_or(0, Vec::len));
                         }
-                        let gm = g["material"].as_object().unwrap();
-                        for m_name in gm.keys() {
-                            let mut vs: Vec<usize> = Vec::new();
-                            let gmv = g["material"][m_name]["values"].as_array();
-                            if gmv.is_some() {
-                                let x = gmv.unwrap();
-                                for each in x {
-                                    let xa = each.as_array().unwrap();
-                                    vs.push(xa.len());
+                        bs.push(tmp);
+                    }
+                    // println!("ms-bs: {:?}", bs);
+                    let gm = g["material"].as_object().into_iter().flatten();
+                    for (m_name, _) in gm {
+                        let mut vs: Vec<Vec<usize>> = Vec::new();
+                        if let Some(x) = g["material"][m_name]["values"].as_array() {
+                            for a1 in x {
+                                let y = a1.as_array().into_iter().flatten();
+                                let mut vs2: Vec<usize> = Vec::new();
+                                for a2 in y {
+                                    let xa = a2.as_array().map_or(&[][..], Vec::as_slice);
+                                    vs2.push(xa.len());
                                     for each2 in xa {
-                                        if (each2.as_u64().is_some())
-                                            && (each2.as_u64().unwrap() > (max_index - 1) as u64)
-                                        {
-                                            ls_errors.push(format!(
+                                        if each2.as_u64().is_some_and(|x| x >= max_index as u64) {
+                                            ls_errors.push_in(theid, format!(
                                                 "Reference in material \"values\" overflows (max={}); #{} and geom-#{} / material-\"{}\"",
-                                                (max_index-1),theid, gi, m_name
+                                                max_index as i64 - 1, theid, gi, m_name
                                             ));
                                         }
                                     }
                                 }
+                                vs.push(vs2);
                             }
-                            let ifvalue = g["material"][m_name]["value"].as_u64();
-                            if ifvalue.is_some() {
-                                if ifvalue.unwrap() > (max_index - 1) as u64 {
-                                    ls_errors.push(format!(
-                                    "Material \"value\" overflow; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
-                                ));
-                                }
-                            } else {
-                                if bs.iter().eq(vs.iter()) == false {
-                                    ls_errors.push(format!(
-                                    "Material \"values\" not same dimension as \"boundaries\"; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
-                                ));
-                                }
-                            }
-                        }
-                    } else if g["type"] == "MultiSolid" || g["type"] == "CompositeSolid" {
-                        //-- length of the sem-surfaces == # of surfaces
-                        let mut bs: Vec<Vec<usize>> = Vec::new();
-                        let solids = g["boundaries"].as_array().unwrap();
-                        for solid in solids {
-                            let asolid = solid.as_array().unwrap();
-                            let mut tmp: Vec<usize> = Vec::new();
-                            for surface in asolid {
-                                tmp.push(surface.as_array().unwrap().len());
-                            }
-                            bs.push(tmp);
                         }
-                        // println!("ms-bs: {:?}", bs);
-                        let gm = g["material"].as_object().unwrap();
-                        for m_name in gm.keys() {
-                            let mut vs: Vec<Vec<usize>> = Vec::new();
-                            let gmv = g["material"][m_name]["values"].as_array();
-                            if gmv.is_some() {
-                                let x = gmv.unwrap();
-                                for a1 in x {
-                                    let y = a1.as_array().unwrap();
-                                    let mut vs2: Vec<usize> = Vec::new();
-                                    for a2 in y {
-                                        let xa = a2.as_array().unwrap();
-                                        vs2.push(xa.len());
-                                        for each2 in xa {
-                                            if (each2.as_u64().is_some())
-                                                && (each2.as_u64().unwrap()
-                                                    > (max_index - 1) as u64)
-                                            {
-                                                ls_errors.push(format!(
-                                                    "Reference in material \"values\" overflows (max={}); #{} and geom-#{} / material-\"{}\"",
-                                                    (max_index-1),theid, gi, m_name
-                                                ));
-                                            }
-                                        }
-                                    }
-                                    vs.push(vs2);
-                                }
+                        if let Some(value) = g["material"][m_name]["value"].as_u64() {
+                            if value >= max_index as u64 {
+                                ls_errors.push_in(theid, format!(
+                                "Material \"value\" overflow; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
+                            ));
                             }
-                            let ifvalue = g["material"][m_name]["value"].as_u64();
-                            if ifvalue.is_some() {
-                                if ifvalue.unwrap() > (max_index - 1) as u64 {
-                                    ls_errors.push(format!(
-                                    "Material \"value\" overflow; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
-                                ));
-                                }
-                            } else {
-                                if bs.iter().eq(vs.iter()) == false {
-                                    ls_errors.push(format!(
-                                    "Material \"values\" not same dimension as \"boundaries\"; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
-                                ));
-                                }
+                        } else {
+                            if bs.iter().eq(vs.iter()) == false {
+                                ls_errors.push_in(theid, format!(
+                                "Material \"values\" not same dimension as \"boundaries\"; #{} / geom-#{} / material-\"{}\"", theid, gi, m_name
+                            ));
                             }
                         }
                     }
-                    gi += 1;
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn textures(&self) -> Result<(), Vec<String>> {
+    fn textures(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        let cj = ctx.cityjson();
         let mut max_i_tex: usize = 0;
-        let mut x = self.j["appearance"]["textures"].as_array();
+        let mut x = ctx.root_property("appearance")["textures"].as_array();
         if x.is_some() {
             max_i_tex = x.unwrap().len();
         }
         let mut max_i_v: usize = 0;
-        x = self.j["appearance"]["vertices-texture"].as_array();
+        x = ctx.root_property("appearance")["vertices-texture"].as_array();
         if x.is_some() {
             max_i_v = x.unwrap().len();
         }
-        let mut ls_errors: Vec<String> = Vec::new();
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+        let Some(cos) = cj["CityObjects"].as_object() else {
+            return;
+        };
         for theid in cos.keys() {
             //-- check geometry
-            let x = self.j["CityObjects"][theid]["geometry"].as_array();
-            if x.is_some() {
-                let gs = x.unwrap();
-                let mut gi = 0;
-                for g in gs {
+            if let Some(x) = cj["CityObjects"][theid]["geometry"].as_array() {
+                for (gi, g) in x.iter().enumerate() {
                     if g.get("texture").is_none() {
                         continue;
                     }
                     if g["type"] == "MultiSurface" || g["type"] == "CompositeSurface" {
-                        let gs: GeomMSu = serde_json::from_value(g.clone()).unwrap();
+                        let Some(gs) =
+                            read_geometry::<GeomMSu>(g, || geometry_pointer(theid, gi), ls_errors)
+                        else {
+                            continue;
+                        };
                         let mut l: Vec<Vec<i64>> = Vec::new();
                         for x in gs.boundaries {
                             let mut l4: Vec<i64> = Vec::new();
@@ -1504,32 +1915,42 @@ impl CJValidator {
                             }
                             l.push(l4);
                         }
-                        let tex = g["texture"].as_object().unwrap();
-                        for m_name in tex.keys() {
-                            let ts: TextureMSu =
-                                serde_json::from_value(g["texture"][m_name].clone()).unwrap();
+                        let tex = g["texture"].as_object().into_iter().flatten();
+                        for (m_name, _) in tex {
+                            let p = || {
+                                format!(
+                                    "{}/texture/{}",
+                                    geometry_pointer(theid, gi),
+                                    parse::escape(m_name)
+                                )
+                            };
+                            let Some(ts) =
+                                read_geometry::<TextureMSu>(&g["texture"][m_name], p, ls_errors)
+                            else {
+                                continue;
+                            };
                             let mut l2: Vec<Vec<i64>> = Vec::new();
                             for x in ts.values {
                                 let mut l3: Vec<i64> = Vec::new();
                                 for mut y in x {
-                                    if y[0].is_none() {
+                                    if y.first().map_or(true, |x| x.is_none()) {
                                         l3.push(-1);
                                     } else {
                                         l3.push(y.len() as i64 - 1);
                                     }
                                     if y.len() > 1 && y[0].is_some() {
-                                        if y[0].unwrap() > (max_i_tex - 1) {
-                                            ls_errors.push(format!(
+                                        if y[0].unwrap() >= max_i_tex {
+                                            ls_errors.push_in(theid, format!(
                                                     "/texture/values/ \"{}\" overflows for texture reference; #{} and geom-#{}",
                                                     y[0].unwrap(), theid, gi
                                                 ));
                                         }
                                         y.remove(0);
                                         for each in y {
-                                            if each.unwrap() > (max_i_v - 1) {
-                                                ls_errors.push(format!(
+                                            if each.is_some_and(|e| e >= max_i_v) {
+                                                ls_errors.push_in(theid, format!(
                                                         "/texture/values/ \"{}\" overflows for texture-vertices (max={}); #{} and geom-#{}",
-                                                        each.unwrap(), (max_i_v - 1), theid, gi
+                                                        each.unwrap(), max_i_v as i64 - 1, theid, gi
                                                     ));
                                             }
                                         }
@@ -1539,8 +1960,11 @@ impl CJValidator {
                             }
                             if l != l2 {
                                 for (i, _e) in l.iter().enumerate() {
-                                    if l[i] != l2[i] && l2[i][0] != -1 {
-                                        ls_errors.push(format!(
+                                    if l2
+                                        .get(i)
+                                        .map_or(true, |x| x != &l[i] && x.first() != Some(&-1))
+                                    {
+                                        ls_errors.push_in(theid, format!(
                                             "/texture/values/ not same structure as /boundaries; #{} and geom-#{} and surface-#{}", theid, gi, i
                                         ));
                                     }
@@ -1548,7 +1972,11 @@ impl CJValidator {
                             }
                         }
                     } else if g["type"] == "Solid" {
-                        let gs: GeomSol = serde_json::from_value(g.clone()).unwrap();
+                        let Some(gs) =
+                            read_geometry::<GeomSol>(g, || geometry_pointer(theid, gi), ls_errors)
+                        else {
+                            continue;
+                        };
                         let mut l: Vec<Vec<i64>> = Vec::new();
                         for x in gs.boundaries {
                             for y in x {
@@ -1559,33 +1987,43 @@ impl CJValidator {
                                 l.push(l4);
                             }
                         }
-                        let tex = g["texture"].as_object().unwrap();
-                        for m_name in tex.keys() {
-                            let ts: TextureSol =
-                                serde_json::from_value(g["texture"][m_name].clone()).unwrap();
+                        let tex = g["texture"].as_object().into_iter().flatten();
+                        for (m_name, _) in tex {
+                            let p = || {
+                                format!(
+                                    "{}/texture/{}",
+                                    geometry_pointer(theid, gi),
+                                    parse::escape(m_name)
+                                )
+                            };
+                            let Some(ts) =
+                                read_geometry::<TextureSol>(&g["texture"][m_name], p, ls_errors)
+                            else {
+                                continue;
+                            };
                             let mut l2: Vec<Vec<i64>> = Vec::new();
                             for x in ts.values {
                                 for y in x {
                                     let mut l3: Vec<i64> = Vec::new();
                                     for mut z in y {
-                                        if z[0].is_none() {
+                                        if z.first().map_or(true, |x| x.is_none()) {
                                             l3.push(-1);
                                         } else {
                                             l3.push(z.len() as i64 - 1);
                                         }
                                         if z.len() > 1 && z[0].is_some() {
-                                            if z[0].unwrap() > (max_i_tex - 1) {
-                                                ls_errors.push(format!(
+                                            if z[0].unwrap() >= max_i_tex {
+                                                ls_errors.push_in(theid, format!(
                                                 "/texture/values/ \"{}\" overflows for texture reference; #{} and geom-#{}",
                                                 z[0].unwrap(), theid, gi
                                             ));
                                             }
                                             z.remove(0);
                                             for each in z {
-                                                if each.unwrap() > (max_i_v - 1) {
-                                                    ls_errors.push(format!(
+                                                if each.is_some_and(|e| e >= max_i_v) {
+                                                    ls_errors.push_in(theid, format!(
                                                     "/texture/values/ \"{}\" overflows for texture-vertices (max={}); #{} and geom-#{}",
-                                                    each.unwrap(), (max_i_v - 1), theid, gi
+                                                    each.unwrap(), max_i_v as i64 - 1, theid, gi
                                                 ));
                                                 }
                                             }
@@ -1596,8 +2034,11 @@ impl CJValidator {
                             }
                             if l != l2 {
                                 for (i, _e) in l.iter().enumerate() {
-                                    if l[i] != l2[i] && l2[i][0] != -1 {
-                                        ls_errors.push(format!(
+                                    if l2
+                                        .get(i)
+                                        .map_or(true, |x| x != &l[i] && x.first() != Some(&-1))
+                                    {
+                                        ls_errors.push_in(theid, format!(
                                             "/texture/values/ not same structure as /boundaries; #{} and geom-#{} and surface-#{}", theid, gi, i
                                         ));
                                     }
@@ -1605,7 +2046,11 @@ impl CJValidator {
                             }
                         }
                     } else if g["type"] == "MultiSolid" || g["type"] == "CompositeSolid" {
-                        let gs: GeomMSol = serde_json::from_value(g.clone()).unwrap();
+                        let Some(gs) =
+                            read_geometry::<GeomMSol>(g, || geometry_pointer(theid, gi), ls_errors)
+                        else {
+                            continue;
+                        };
                         let mut l: Vec<Vec<i64>> = Vec::new();
                         for x in gs.boundaries {
                             for y in x {
@@ -1618,34 +2063,44 @@ impl CJValidator {
                                 }
                             }
                         }
-                        let tex = g["texture"].as_object().unwrap();
-                        for m_name in tex.keys() {
-                            let ts: TextureMSol =
-                                serde_json::from_value(g["texture"][m_name].clone()).unwrap();
+                        let tex = g["texture"].as_object().into_iter().flatten();
+                        for (m_name, _) in tex {
+                            let p = || {
+                                format!(
+                                    "{}/texture/{}",
+                                    geometry_pointer(theid, gi),
+                                    parse::escape(m_name)
+                                )
+                            };
+                            let Some(ts) =
+                                read_geometry::<TextureMSol>(&g["texture"][m_name], p, ls_errors)
+                            else {
+                                continue;
+                            };
                             let mut l2: Vec<Vec<i64>> = Vec::new();
                             for x in ts.values {
                                 for y in x {
                                     for z in y {
                                         let mut l3: Vec<i64> = Vec::new();
                                         for mut w in z {
-                                            if w[0].is_none() {
+                                            if w.first().map_or(true, |x| x.is_none()) {
                                                 l3.push(-1);
                                             } else {
                                                 l3.push(w.len() as i64 - 1);
                                             }
                                             if w.len() > 1 && w[0].is_some() {
-                                                if w[0].unwrap() > (max_i_tex - 1) {
-                                                    ls_errors.push(format!(
+                                                if w[0].unwrap() >= max_i_tex {
+                                                    ls_errors.push_in(theid, format!(
                                                     "/texture/values/ \"{}\" overflows for texture reference; #{} and geom-#{}",
                                                     w[0].unwrap(), theid, gi
                                                 ));
                                                 }
                                                 w.remove(0);
                                                 for each in w {
-                                                    if each.unwrap() > (max_i_v - 1) {
-                                                        ls_errors.push(format!(
+                                                    if each.is_some_and(|e| e >= max_i_v) {
+                                                        ls_errors.push_in(theid, format!(
                                                         "/texture/values/ \"{}\" overflows for texture-vertices (max={}); #{} and geom-#{}",
-                                                        each.unwrap(), (max_i_v - 1), theid, gi
+                                                        each.unwrap(), max_i_v as i64 - 1, theid, gi
                                                     ));
                                                     }
                                                 }
@@ -1657,8 +2112,11 @@ impl CJValidator {
                             }
                             if l != l2 {
                                 for (i, _e) in l.iter().enumerate() {
-                                    if l[i] != l2[i] && l2[i][0] != -1 {
-                                        ls_errors.push(format!(
+                                    if l2
+                                        .get(i)
+                                        .map_or(true, |x| x != &l[i] && x.first() != Some(&-1))
+                                    {
+                                        ls_errors.push_in(theid, format!(
                                             "/texture/values/ not same structure as /boundaries; #{} and geom-#{} and surface-#{}", theid, gi, i
                                         ));
                                     }
@@ -1666,92 +2124,125 @@ impl CJValidator {
                             }
                         }
                     }
-                    gi += 1;
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
     }
 
-    fn wrong_vertex_index(&self) -> Result<(), Vec<String>> {
-        let max_index: usize = self.j.get("vertices").unwrap().as_array().unwrap().len();
-        let mut ls_errors: Vec<String> = Vec::new();
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
+    fn wrong_vertex_index(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        let cj = ctx.cityjson();
+        let max_index: usize = cj["vertices"].as_array().map_or(0, Vec::len);
+        let max_template: usize =
+            match ctx.root_property("geometry-templates")["templates"].as_array() {
+                Some(x) => x.len(),
+                None => 0,
+            };
+        let Some(cos) = cj["CityObjects"].as_object() else {
+            return;
+        };
         for key in cos.keys() {
             //-- check geometry
-            let x = self.j["CityObjects"][key]["geometry"].as_array();
-            if x.is_some() {
-                for g in x.unwrap() {
+            if let Some(x) = cj["CityObjects"][key]["geometry"].as_array() {
+                for (gi, g) in x.iter().enumerate() {
                     if g["type"] == "MultiPoint" {
-                        let a: GeomMPo = serde_json::from_value(g.clone()).unwrap();
+                        let Some(a) =
+                            read_geometry::<GeomMPo>(g, || geometry_pointer(key, gi), ls_errors)
+                        else {
+                            continue;
+                        };
                         for each in a.boundaries {
                             if each >= max_index {
-                                let s2 = format!("Vertices {} don't exist", each);
-                                ls_errors.push(s2);
+                                let s2 = format!("Vertices {} don't exist (in #{})", each, key);
+                                ls_errors.push_in(key, s2);
                             }
                         }
                     } else if g["type"] == "MultiLineString" {
-                        let a: GeomMLS = serde_json::from_value(g.clone()).unwrap();
+                        let Some(a) =
+                            read_geometry::<GeomMLS>(g, || geometry_pointer(key, gi), ls_errors)
+                        else {
+                            continue;
+                        };
                         for l in a.boundaries {
                             for each in l {
                                 if each >= max_index {
-                                    let s2 = format!("Vertices {} don't exist", each);
-                                    ls_errors.push(s2);
+                                    let s2 = format!("Vertices {} don't exist (in #{})", each, key);
+                                    ls_errors.push_in(key, s2);
                                 }
                             }
                         }
                     } else if g["type"] == "MultiSurface" || g["type"] == "CompositeSurface" {
-                        let a: GeomMSu = serde_json::from_value(g.clone()).unwrap();
-                        let re =
-                            above_max_index_msu(&a.boundaries, max_index, "vertices".to_string());
-                        if re.is_err() {
-                            ls_errors.push(re.err().unwrap());
+                        let Some(a) =
+                            read_geometry::<GeomMSu>(g, || geometry_pointer(key, gi), ls_errors)
+                        else {
+                            continue;
+                        };
+                        for each in a.boundaries.iter().flatten().flatten() {
+                            if *each >= max_index {
+                                let s2 = format!("Vertices {} don't exist (in #{})", each, key);
+                                ls_errors.push_in(key, s2);
+                            }
                         }
                     } else if g["type"] == "Solid" {
-                        let a: GeomSol = serde_json::from_value(g.clone()).unwrap();
-                        let re =
-                            above_max_index_sol(&a.boundaries, max_index, "vertices".to_string());
-                        if re.is_err() {
-                            ls_errors.push(re.err().unwrap());
+                        let Some(a) =
+                            read_geometry::<GeomSol>(g, || geometry_pointer(key, gi), ls_errors)
+                        else {
+                            continue;
+                        };
+                        for each in a.boundaries.iter().flatten().flatten().flatten() {
+                            if *each >= max_index {
+                                let s2 = format!("Vertices {} don't exist (in #{})", each, key);
+                                ls_errors.push_in(key, s2);
+                            }
                         }
                     } else if g["type"] == "MultiSolid" || g["type"] == "CompositeSolid" {
-                        let a: GeomMSol = serde_json::from_value(g.clone()).unwrap();
-                        let re =
-                            above_max_index_msol(&a.boundaries, max_index, "vertices".to_string());
-                        if re.is_err() {
-                            ls_errors.push(re.err().unwrap());
+                        let Some(a) =
+                            read_geometry::<GeomMSol>(g, || geometry_pointer(key, gi), ls_errors)
+                        else {
+                            continue;
+                        };
+                        for each in a.boundaries.iter().flatten().flatten().flatten().flatten() {
+                            if *each >= max_index {
+                                let s2 = format!("Vertices {} don't exist (in #{})", each, key);
+                                ls_errors.push_in(key, s2);
+                            }
                         }
                     } else if g["type"] == "GeometryInstance" {
-                        let a: GeomMPo = serde_json::from_value(g.clone()).unwrap();
+                        let Some(a) =
+                            read_geometry::<GeomMPo>(g, || geometry_pointer(key, gi), ls_errors)
+                        else {
+                            continue;
+                        };
                         for each in a.boundaries {
                             if each >= max_index {
                                 let s2 = format!("Vertex {} doesn't exist (in #{})", each, key);
-                                ls_errors.push(s2);
+                                ls_errors.push_in(key, s2);
+                            }
+                        }
+                        if let Some(t) = g["template"].as_u64() {
+                            if t as usize >= max_template {
+                                let s2 =
+                                    format!("Geometry template {} doesn't exist (in #{})", t, key);
+                                ls_errors.push_in(key, s2);
                             }
                         }
                     }
                 }
             }
             //-- check address
-            if self.j["CityObjects"][key]["type"] == "Building"
-                || self.j["CityObjects"][key]["type"] == "BuildingPart"
-                || self.j["CityObjects"][key]["type"] == "BuildingUnit"
-                || self.j["CityObjects"][key]["type"] == "Bridge"
-                || self.j["CityObjects"][key]["type"] == "BridgePart"
+            if cj["CityObjects"][key]["type"] == "Building"
+                || cj["CityObjects"][key]["type"] == "BuildingPart"
+                || cj["CityObjects"][key]["type"] == "BuildingUnit"
+                || cj["CityObjects"][key]["type"] == "Bridge"
+                || cj["CityObjects"][key]["type"] == "BridgePart"
             {
-                let x = self.j["CityObjects"][key]["address"].as_array();
+                let x = cj["CityObjects"][key]["address"].as_array();
                 if x.is_some() {
                     for ad in x.unwrap() {
-                        let t = ad.pointer("/location/boundaries");
-                        if t.is_some() {
-                            let i = t.unwrap().get(0).unwrap().as_u64().unwrap();
+                        let t = ad.pointer("/location/boundaries/0");
+                        if let Some(i) = t.and_then(|x| x.as_u64()) {
                             if (i as usize) >= max_index {
-                                let s2 = format!("Vertices {} don't exist", i);
-                                ls_errors.push(s2);
+                                let s2 = format!("Vertices {} don't exist (in #{})", i, key);
+                                ls_errors.push_in(key, s2);
                             }
                         }
                     }
@@ -1759,17 +2250,22 @@ impl CJValidator {
             }
         }
         //-- check geometry-templates
-        let gts = self.j["geometry-templates"].as_object();
+        let gts = cj["geometry-templates"].as_object();
         if gts.is_some() {
-            let max_index: usize = self.j["geometry-templates"]["vertices-templates"]
+            let max_index: usize = cj["geometry-templates"]["vertices-templates"]
                 .as_array()
-                .unwrap()
-                .len();
-            let ts = self.j["geometry-templates"]["templates"].as_array();
+                .map_or(0, |x| x.len());
+            let ts = cj["geometry-templates"]["templates"].as_array();
             if ts.is_some() {
-                for t in ts.unwrap() {
+                for (ti, t) in ts.unwrap().iter().enumerate() {
                     if t["type"] == "MultiPoint" {
-                        let a: GeomMPo = serde_json::from_value(t.clone()).unwrap();
+                        let Some(a) = read_geometry::<GeomMPo>(
+                            t,
+                            || format!("/geometry-templates/templates/{}", ti),
+                            ls_errors,
+                        ) else {
+                            continue;
+                        };
                         for each in a.boundaries {
                             if each >= max_index {
                                 let s2 = format!("vertices-templates {} don't exist", each);
@@ -1777,7 +2273,13 @@ impl CJValidator {
                             }
                         }
                     } else if t["type"] == "MultiLineString" {
-                        let a: GeomMLS = serde_json::from_value(t.clone()).unwrap();
+                        let Some(a) = read_geometry::<GeomMLS>(
+                            t,
+                            || format!("/geometry-templates/templates/{}", ti),
+                            ls_errors,
+                        ) else {
+                            continue;
+                        };
                         for l in a.boundaries {
                             for each in l {
                                 if each >= max_index {
@@ -1787,37 +2289,55 @@ impl CJValidator {
                             }
                         }
                     } else if t["type"] == "MultiSurface" || t["type"] == "CompositeSurface" {
-                        let a: GeomMSu = serde_json::from_value(t.clone()).unwrap();
-                        let re = above_max_index_msu(
-                            &a.boundaries,
-                            max_index,
-                            "vertices-templates".to_string(),
-                        );
-                        if re.is_err() {
-                            ls_errors.push(re.err().unwrap());
+                        let Some(a) = read_geometry::<GeomMSu>(
+                            t,
+                            || format!("/geometry-templates/templates/{}", ti),
+                            ls_errors,
+                        ) else {
+                            continue;
+                        };
+                        for each in a.boundaries.iter().flatten().flatten() {
+                            if *each >= max_index {
+                                let s2 = format!("vertices-templates {} don't exist", each);
+                                ls_errors.push(s2);
+                            }
                         }
                     } else if t["type"] == "Solid" {
-                        let a: GeomSol = serde_json::from_value(t.clone()).unwrap();
-                        let re = above_max_index_sol(
-                            &a.boundaries,
-                            max_index,
-                            "vertices-templates".to_string(),
-                        );
-                        if re.is_err() {
-                            ls_errors.push(re.err().unwrap());
+                        let Some(a) = read_geometry::<GeomSol>(
+                            t,
+                            || format!("/geometry-templates/templates/{}", ti),
+                            ls_errors,
+                        ) else {
+                            continue;
+                        };
+                        for each in a.boundaries.iter().flatten().flatten().flatten() {
+                            if *each >= max_index {
+                                let s2 = format!("vertices-templates {} don't exist", each);
+                                ls_errors.push(s2);
+                            }
                         }
                     } else if t["type"] == "MultiSolid" || t["type"] == "CompositeSolid" {
-                        let a: GeomMSol = serde_json::from_value(t.clone()).unwrap();
-                        let re = above_max_index_msol(
-                            &a.boundaries,
-                            max_index,
-                            "vertices-templates".to_string(),
-                        );
-                        if re.is_err() {
-                            ls_errors.push(re.err().unwrap());
+                        let Some(a) = read_geometry::<GeomMSol>(
+                            t,
+                            || format!("/geometry-templates/templates/{}", ti),
+                            ls_errors,
+                        ) else {
+                            continue;
+                        };
+                        for each in a.boundaries.iter().flatten().flatten().flatten().flatten() {
+                            if *each >= max_index {
+                                let s2 = format!("vertices-templates {} don't exist", each);
+                                ls_errors.push(s2);
+                            }
                         }
                     } else if t["type"] == "GeometryInstance" {
-                        let a: GeomMPo = serde_json::from_value(t.clone()).unwrap();
+                        let Some(a) = read_geometry::<GeomMPo>(
+                            t,
+                            || format!("/geometry-templates/templates/{}", ti),
+                            ls_errors,
+                        ) else {
+                            continue;
+                        };
                         for each in a.boundaries {
                             if each >= max_index {
                                 let s2 = format!("vertices-templates {} doesn't exist", each);
@@ -1828,225 +2348,224 @@ impl CJValidator {
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
+    }
+
+    fn unused_vertices(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        let cj = ctx.cityjson();
+        let mut uniques: HashSet<usize> = HashSet::new();
+        for (_, co) in ctx.cityobjects() {
+            collect_indices_cityobject(co, &mut uniques);
+        }
+        //-- uniques can have indices that don't exist (wrong_vertex_index)
+        let total = cj["vertices"].as_array().map_or(0, Vec::len);
+        let unused: Vec<usize> = (0..total).filter(|i| !uniques.contains(i)).collect();
+        if unused.len() > 5 {
+            ls_errors.push(format!("{} vertices are unused", unused.len()));
         } else {
-            Err(ls_errors)
+            for each in unused {
+                ls_errors.push(format!("Vertex #{} is unused", each));
+            }
         }
     }
 
-    fn unused_vertices(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
-        let mut uniques: HashSet<usize> = HashSet::new();
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
-        for key in cos.keys() {
-            //-- check geometry
-            let x = self.j["CityObjects"][key]["geometry"].as_array();
-            if x.is_some() {
-                let gs = x.unwrap();
-                for g in gs {
-                    if g["type"] == "MultiPoint" {
-                        let a: GeomMPo = serde_json::from_value(g.clone()).unwrap();
-                        for each in a.boundaries {
-                            uniques.insert(each);
-                        }
-                    } else if g["type"] == "MultiLineString" {
-                        let a: GeomMLS = serde_json::from_value(g.clone()).unwrap();
-                        for l in a.boundaries {
-                            for each in l {
-                                uniques.insert(each);
-                            }
+    fn semantics_arrays(ctx: &ValidationContext, ls_errors: &mut ErrorList) {
+        let cj = ctx.cityjson();
+        let Some(cos) = cj["CityObjects"].as_object() else {
+            return;
+        };
+        for theid in cos.keys() {
+            let gs = cj["CityObjects"][theid]["geometry"].as_array();
+            for (gi, g) in gs.into_iter().flatten().enumerate() {
+                if g.get("semantics").is_none() {
+                    continue;
+                }
+                if g["type"] == "MultiPoint"
+                    || g["type"] == "MultiLineString"
+                    || g["type"] == "MultiSurface"
+                    || g["type"] == "CompositeSurface"
+                {
+                    //-- length of the sem-surfaces == # of surfaces
+                    if g["boundaries"].as_array().map_or(0, Vec::len)
+                        != g["semantics"]["values"].as_array().map_or(0, Vec::len)
+                    {
+                        ls_errors.push_in(theid, format!(
+                            "Semantic \"values\" not same dimension as \"boundaries\"; #{} and geom-#{}", theid, gi
+                        ));
+                    }
+                    //-- values in "values"
+                    let a = g["semantics"]["surfaces"].as_array().map_or(0, Vec::len);
+                    for i in g["semantics"]["values"].as_array().into_iter().flatten() {
+                        if i.is_null() {
+                            continue;
                         }
-                    } else if g["type"] == "MultiSurface" || g["type"] == "CompositeSurface" {
-                        let gv: GeomMSu = serde_json::from_value(g.clone()).unwrap();
-                        collect_indices_msu(&gv.boundaries, &mut uniques);
-                    } else if g["type"] == "Solid" {
-                        let gv: GeomSol = serde_json::from_value(g.clone()).unwrap();
-                        collect_indices_sol(&gv.boundaries, &mut uniques);
-                    } else if g["type"] == "MultiSolid" || g["type"] == "CompositeSolid" {
-                        let gv: GeomMSol = serde_json::from_value(g.clone()).unwrap();
-                        collect_indices_msol(&gv.boundaries, &mut uniques);
-                    } else if g["type"] == "GeometryInstance" {
-                        let a: GeomMPo = serde_json::from_value(g.clone()).unwrap();
-                        for each in a.boundaries {
-                            uniques.insert(each);
+                        if i.as_u64().map_or(true, |x| x >= a as u64) {
+                            ls_errors.push_in(
+                                theid,
+                                format!(
+                                    "Reference in semantic \"values\" overflows; #{} and geom-#{}",
+                                    theid, gi
+                                ),
+                            );
                         }
                     }
                 }
-            }
-            //-- check address
-            if self.j["CityObjects"][key]["type"] == "Building"
-                || self.j["CityObjects"][key]["type"] == "BuildingPart"
-                || self.j["CityObjects"][key]["type"] == "BuildingUnit"
-                || self.j["CityObjects"][key]["type"] == "Bridge"
-                || self.j["CityObjects"][key]["type"] == "BridgePart"
-            {
-                let x = self.j["CityObjects"][key]["address"].as_array();
-                if x.is_some() {
-                    for ad in x.unwrap() {
-                        let t = ad.pointer("/location/boundaries");
-                        if t.is_some() {
-                            let i = t.unwrap().get(0).unwrap().as_u64().unwrap();
-                            uniques.insert(i as usize);
-                        }
+                if g["type"] == "Solid" {
+                    //-- length of the sem-surfaces == # of surfaces
+                    let mut bs: Vec<usize> = Vec::new();
+                    let shells = g["boundaries"].as_array().into_iter().flatten();
+                    for surface in shells {
+                        bs.push(surface.as_array().map_or(0, Vec::len));
                     }
-                }
-            }
-        }
-        let noorphans = self.j["vertices"].as_array().unwrap().len() - uniques.len();
-        if noorphans > 0 {
-            if noorphans > 5 {
-                ls_errors.push(format!("{} vertices are unused", noorphans));
-            } else {
-                let total = self.j["vertices"].as_array().unwrap().len();
-                for each in 0..total {
-                    if !uniques.contains(&each) {
-                        ls_errors.push(format!("Vertex #{} is unused", each));
+                    // println!("bs: {:?}", bs);
+                    let mut vs: Vec<usize> = Vec::new();
+                    let tmp = g["semantics"]["values"].as_array().into_iter().flatten();
+                    for each in tmp {
+                        vs.push(each.as_array().map_or(0, Vec::len));
                     }
-                }
-            }
-        }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
-        }
-    }
-
-    fn semantics_arrays(&self) -> Result<(), Vec<String>> {
-        let mut ls_errors: Vec<String> = Vec::new();
-        let cos = self.j.get("CityObjects").unwrap().as_object().unwrap();
-        for theid in cos.keys() {
-            let x = self.j["CityObjects"][theid]["geometry"].as_array();
-            if x.is_some() {
-                let gs = x.unwrap();
-                let mut gi = 0;
-                for g in gs {
-                    if g.get("semantics").is_none() {
-                        continue;
+                    // println!("vs: {:?}", vs);
+                    // println!("eq: {:?}", bs.iter().eq(vs.iter()));
+                    if bs.iter().eq(vs.iter()) == false {
+                        ls_errors.push_in(theid, format!(
+                            "Semantic \"values\" not same dimension as \"boundaries\"; #{} and geom-#{}", theid, gi
+                        ));
                     }
-                    if g["type"] == "MultiPoint"
-                        || g["type"] == "MultiLineString"
-                        || g["type"] == "MultiSurface"
-                        || g["type"] == "CompositeSurface"
-                    {
-                        //-- length of the sem-surfaces == # of surfaces
-                        if g["boundaries"].as_array().unwrap().len()
-                            != g["semantics"]["values"].as_array().unwrap().len()
-                        {
-                            ls_errors.push(format!(
-                                "Semantic \"values\" not same dimension as \"boundaries\"; #{} and geom-#{}", theid, gi
-                            ));
-                        }
-                        //-- values in "values"
-                        let a = g["semantics"]["surfaces"].as_array().unwrap().len();
-                        for i in g["semantics"]["values"].as_array().unwrap() {
-                            if i.is_null() {
+                    //-- values in "values"
+                    let a = g["semantics"]["surfaces"].as_array().map_or(0, Vec::len);
+                    for i in g["semantics"]["values"].as_array().into_iter().flatten() {
+                        let ai = i.as_array().into_iter().flatten();
+                        for j in ai {
+                            if j.is_null() {
                                 continue;
                             }
-                            if i.as_u64().unwrap() > (a - 1) as u64 {
-                                ls_errors.push(format!(
+                            if j.as_u64().map_or(true, |x| x >= a as u64) {
+                                ls_errors.push_in(
+                                    theid,
+                                    format!(
                                     "Reference in semantic \"values\" overflows; #{} and geom-#{}",
                                     theid, gi
-                                ));
+                                ),
+                                );
                             }
                         }
                     }
-                    if g["type"] == "Solid" {
-                        //-- length of the sem-surfaces == # of surfaces
-                        let mut bs: Vec<usize> = Vec::new();
-                        let shells = g["boundaries"].as_array().unwrap();
-                        for surface in shells {
-                            bs.push(surface.as_array().unwrap().len());
-                        }
-                        // println!("bs: {:?}", bs);
-                        let mut vs: Vec<usize> = Vec::new();
-                        let tmp = g["semantics"]["values"].as_array().unwrap();
-                        for each in tmp {
-                            vs.push(each.as_array().unwrap().len());
+                }
+                if g["type"] == "MultiSolid" || g["type"] == "CompositeSolid" {
+                    //-- length of the sem-surfaces == # of surfaces
+                    let mut bs: Vec<Vec<usize>> = Vec::new();
+                    let solids = g["boundaries"].as_array().into_iter().flatten();
+                    for solid in solids {
+                        let asolid = solid.as_array().into_iter().flatten();
+                        let mut tmp: Vec<usize> = Vec::new();
+                        for surface in asolid {
+                            tmp.push(surface.as_array().map_or(0, Vec::len));
                         }
-                        // println!("vs: {:?}", vs);
-                        // println!("eq: {:?}", bs.iter().eq(vs.iter()));
-                        if bs.iter().eq(vs.iter()) == false {
-                            ls_errors.push(format!(
-                                "Semantic \"values\" not same dimension as \"boundaries\"; #{} and geom-#{}", theid, gi
-                            ));
+                        bs.push(tmp);
+                    }
+                    // println!("ms-bs: {:?}", bs);
+                    let mut vs: Vec<Vec<usize>> = Vec::new();
+                    let a = g["semantics"]["values"].as_array().into_iter().flatten();
+                    for i in a {
+                        let mut tmp: Vec<usize> = Vec::new();
+                        let b = i.as_array().into_iter().flatten();
+                        for j in b {
+                            tmp.push(j.as_array().map_or(0, Vec::len));
                         }
-                        //-- values in "values"
-                        let a = g["semantics"]["surfaces"].as_array().unwrap().len();
-                        for i in g["semantics"]["values"].as_array().unwrap() {
-                            let ai = i.as_array().unwrap();
-                            for j in ai {
-                                if j.is_null() {
+                        vs.push(tmp);
+                    }
+                    // println!("ms-vs: {:?}", vs);
+                    // println!("eq: {:?}", bs.iter().eq(vs.iter()));
+                    if bs.iter().eq(vs.iter()) == false {
+                        ls_errors.push_in(theid, format!(
+                            "Semantic \"values\" not same dimension as \"boundaries\"; #{} and geom-#{}", theid, gi
+                        ));
+                    }
+                    //-- values in "values"
+                    let a = g["semantics"]["surfaces"].as_array().map_or(0, Vec::len);
+                    for i in g["semantics"]["values"].as_array().into_iter().flatten() {
+                        let ai = i.as_array().into_iter().flatten();
+                        for j in ai {
+                            let aj = j.as_array().into_iter().flatten();
+                            for k in aj {
+                                if k.is_null() {
                                     continue;
                                 }
-                                if j.as_u64().unwrap() > (a - 1) as u64 {
-                                    ls_errors.push(format!(
-                                        "Reference in semantic \"values\" overflows; #{} and geom-#{}",
-                                        theid, gi
-                                    ));
-                                }
-                            }
-                        }
-                    }
-                    if g["type"] == "MultiSolid" || g["type"] == "CompositeSolid" {
-                        //-- length of the sem-surfaces == # of surfaces
-                        let mut bs: Vec<Vec<usize>> = Vec::new();
-                        let solids = g["boundaries"].as_array().unwrap();
-                        for solid in solids {
-                            let asolid = solid.as_array().unwrap();
-                            let mut tmp: Vec<usize> = Vec::new();
-                            for surface in asolid {
-                                tmp.push(surface.as_array().unwrap().len());
-                            }
-                            bs.push(tmp);
-                        }
-                        // println!("ms-bs: {:?}", bs);
-                        let mut vs: Vec<Vec<usize>> = Vec::new();
-                        let a = g["semantics"]["values"].as_array().unwrap();
-                        for i in a {
-                            let mut tmp: Vec<usize> = Vec::new();
-                            let b = i.as_array().unwrap();
-                            for j in b {
-                                tmp.push(j.as_array().unwrap().len());
-                            }
-                            vs.push(tmp);
-                        }
-                        // println!("ms-vs: {:?}", vs);
-                        // println!("eq: {:?}", bs.iter().eq(vs.iter()));
-                        if bs.iter().eq(vs.iter()) == false {
-                            ls_errors.push(format!(
-                                "Semantic \"values\" not same dimension as \"boundaries\"; #{} and geom-#{}", theid, gi
-                            ));
-                        }
-                        //-- values in "values"
-                        let a = g["semantics"]["surfaces"].as_array().unwrap().len();
-                        for i in g["semantics"]["values"].as_array().unwrap() {
-                            let ai = i.as_array().unwrap();
-                            for j in ai {
-                                let aj = j.as_array().unwrap();
-                                for k in aj {
-                                    if k.is_null() {
-                                        continue;
-                                    }
-                                    if k.as_u64().unwrap() > (a - 1) as u64 {
-                                        ls_errors.push(format!(
-                                        "Reference in semantic \"values\" overflows; #{} and geom-#{}",
-                                        theid, gi
-                                    ));
-                                    }
+                                if k.as_u64().map_or(true, |x| x >= a as u64) {
+                                    ls_errors.push_in(
+                                        theid,
+                                        format!(
+                                    "Reference in semantic \"values\" overflows; #{} and geom-#{}",
+                                    theid, gi
+                                ),
+                                    );
                                 }
                             }
                         }
                     }
-                    gi += 1;
                 }
             }
         }
-        if ls_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(ls_errors)
+    }
+}
+
+/// The indices of the vertices used by a CityObject (its geometries and its
+/// address)
+fn collect_indices_cityobject(co: &Value, uniques: &mut HashSet<usize>) {
+    //-- check geometry
+    let gs = co["geometry"].as_array();
+    for g in gs.into_iter().flatten() {
+        if g["type"] == "MultiPoint" {
+            let Ok(a) = GeomMPo::deserialize(g) else {
+                continue;
+            };
+            for each in a.boundaries {
+                uniques.insert(each);
+            }
+        } else if g["type"] == "MultiLineString" {
+            let Ok(a) = GeomMLS::deserialize(g) else {
+                continue;
+            };
+            for l in a.boundaries {
+                for each in l {
+                    uniques.insert(each);
+                }
+            }
+        } else if g["type"] == "MultiSurface" || g["type"] == "CompositeSurface" {
+            let Ok(gv) = GeomMSu::deserialize(g) else {
+                continue;
+            };
+            collect_indices_msu(&gv.boundaries, uniques);
+        } else if g["type"] == "Solid" {
+            let Ok(gv) = GeomSol::deserialize(g) else {
+                continue;
+            };
+            collect_indices_sol(&gv.boundaries, uniques);
+        } else if g["type"] == "MultiSolid" || g["type"] == "CompositeSolid" {
+            let Ok(gv) = GeomMSol::deserialize(g) else {
+                continue;
+            };
+            collect_indices_msol(&gv.boundaries, uniques);
+        } else if g["type"] == "GeometryInstance" {
+            let Ok(a) = GeomMPo::deserialize(g) else {
+                continue;
+            };
+            for each in a.boundaries {
+                uniques.insert(each);
+            }
+        }
+    }
+    //-- check address
+    if co["type"] == "Building"
+        || co["type"] == "BuildingPart"
+        || co["type"] == "BuildingUnit"
+        || co["type"] == "Bridge"
+        || co["type"] == "BridgePart"
+    {
+        let ads = co["address"].as_array();
+        for ad in ads.into_iter().flatten() {
+            let t = ad.pointer("/location/boundaries/0");
+            if let Some(i) = t.and_then(|x| x.as_u64()) {
+                uniques.insert(i as usize);
+            }
         }
     }
 }
@@ -2087,95 +2606,93 @@ fn collect_indices_msol(a: &Vec<Vec<Vec<Vec<Vec<usize>>>>>, uniques: &mut HashSe
     }
 }
 
-fn above_max_index_msu(
-    a: &Vec<Vec<Vec<usize>>>,
-    max_index: usize,
-    whichvertex: String,
-) -> Result<(), String> {
-    let mut r: Vec<usize> = vec![];
-    for x in a {
-        for y in x {
-            for z in y {
-                if z >= &max_index {
-                    r.push(*z);
-                }
+/// The definitions of an Extension: (kind, name, schema), the name of an
+/// extra attribute is "{CityObject type}/{attribute}"
+fn extension_definitions(jext: &Value) -> Vec<(&'static str, String, &Value)> {
+    let mut re: Vec<(&'static str, String, &Value)> = Vec::new();
+    for kind in [
+        "extraCityObjects",
+        "extraRootProperties",
+        "extraSemanticSurfaces",
+    ] {
+        if let Some(x) = jext[kind].as_object() {
+            for (k, schema) in x {
+                re.push((kind, k.to_string(), schema));
             }
         }
     }
-    if r.is_empty() {
-        Ok(())
-    } else {
-        let mut s: String = "".to_string();
-        for each in r {
-            s += "#";
-            s += &each.to_string();
-            s += "/";
-        }
-        let s2 = format!("{} {} don't exist", whichvertex, s);
-        Err(s2)
-    }
-}
-
-fn above_max_index_sol(
-    a: &Vec<Vec<Vec<Vec<usize>>>>,
-    max_index: usize,
-    whichvertex: String,
-) -> Result<(), String> {
-    let mut r: Vec<usize> = vec![];
-    for x in a {
-        for y in x {
-            for z in y {
-                for w in z {
-                    if w >= &max_index {
-                        r.push(*w);
-                    }
+    if let Some(x) = jext["extraAttributes"].as_object() {
+        for (cotype, atts) in x {
+            if let Some(atts) = atts.as_object() {
+                for (att, schema) in atts {
+                    re.push(("extraAttributes", format!("{}/{}", cotype, att), schema));
                 }
             }
         }
     }
-    if r.is_empty() {
-        Ok(())
-    } else {
-        let mut s: String = "".to_string();
-        for each in r {
-            s += "#";
-            s += &each.to_string();
-            s += "/";
-        }
-        let s2 = format!("{} {} don't exist", whichvertex, s);
-        Err(s2)
-    }
+    re
 }
 
-fn above_max_index_msol(
-    a: &Vec<Vec<Vec<Vec<Vec<usize>>>>>,
-    max_index: usize,
-    whichvertex: String,
-) -> Result<(), String> {
-    let mut r: Vec<usize> = vec![];
-    for x in a {
-        for y in x {
-            for z in y {
-                for w in z {
-                    for q in w {
-                        if q >= &max_index {
-                            r.push(*q);
-                        }
-                    }
-                }
-            }
+/// The URL of an Extension file ("url" in v1.0, "uri" afterwards)
+fn extension_url(jext: &Value) -> &str {
+    jext["uri"].as_str().or(jext["url"].as_str()).unwrap_or("")
+}
+
+/// Reads a geometry (or its textures), the schema doesn't guarantee that the
+/// indices are all usize (eg 1e20 is an integer for the schema). The JSON
+/// Pointer of the geometry is only built for the error.
+fn read_geometry<'a, T: Deserialize<'a>>(
+    g: &'a Value,
+    pointer: impl FnOnce() -> String,
+    ls_errors: &mut ErrorList,
+) -> Option<T> {
+    match T::deserialize(g) {
+        Ok(x) => Some(x),
+        Err(e) => {
+            let p = pointer();
+            ls_errors.push_at(&p, format!("Geometry cannot be read: {} [path:{}]", e, p));
+            None
         }
     }
-    if r.is_empty() {
-        return Ok(());
+}
+
+/// The JSON Pointer of a geometry of a CityObject
+fn geometry_pointer(id: &str, gi: usize) -> String {
+    format!("/CityObjects/{}/geometry/{}", parse::escape(id), gi)
+}
+
+/// Adds the result of a check to its summary, `cascading` if an earlier
+/// check has errors
+fn set_check_result(
+    v: &CJValidator,
+    vsum: &mut IndexMap<String, ValSummary>,
+    check: &str,
+    errors: ErrorList,
+    cascading: bool,
+) {
+    let summ = vsum.get_mut(check).unwrap();
+    if errors.is_empty() {
+        summ.set_validity(true);
     } else {
-        let mut s: String = "".to_string();
-        for each in r {
-            s += "#";
-            s += &each.to_string();
-            s += "/";
+        summ.cascading = cascading;
+    }
+    let (diagnostics, more, ignored, nignored) = errors.into_parts();
+    for mut d in diagnostics {
+        if d.location.is_none() {
+            d.location = v.locate(&d);
         }
-        let s2 = format!("{} {} don't exist", whichvertex, s);
-        return Err(s2);
+        summ.add_diagnostic(d);
+    }
+    if more > 0 {
+        summ.more = more;
+        summ.errors
+            .push(format!("... and {} more", check::thousands(more)));
     }
+    summ.ignored = ignored;
+    summ.nignored = nignored;
+}
+
+/// Returns true if a check that is an error (not a warning) failed
+fn has_errors(vsum: &IndexMap<String, ValSummary>) -> bool {
+    vsum.values().any(|s| s.has_errors() && !s.is_warning())
 }
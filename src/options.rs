@@ -0,0 +1,274 @@
+//! Which checks are performed by [`crate::CJValidator::validate`], and their
+//! severity.
+//!
+//! By default all the checks are performed, *extra_root_properties*,
+//! *duplicate_vertices*, *unused_vertices* and *extension_versions* are
+//! warnings and the others are errors. A [`ValidationOptions`] can disable
+//! checks or change their severity, it can be read from a TOML file (eg
+//! `cjval.toml`, with the cargo feature `toml`):
+//!
+//! ```toml
+//! name = "delivery-contract"
+//!
+//! [rules]
+//! duplicate_vertices = "error"
+//! unused_vertices = "off"
+//! ```
+//!
+//! By default the validation stops after errors in the schema or in the
+//! Extensions, and the warnings are not checked if there are errors (to avoid
+//! cascading errors). In the *exhaustive* mode all the checks are performed,
+//! and the results that may be caused by earlier errors are marked (see
+//! [`crate::ValSummary::may_be_cascading`]).
+//!
+//! The identical errors of a check are grouped, and a limit of different
+//! errors listed can be set (`error_limit = 100`, 0 for no limit), the others
+//! are then counted ("... and 1,203,442 more"). All of them are listed by
+//! default (`cjval` lists only the first 100).
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// The checks, in the order they are performed, and their default severity
+static CHECKS: [(&str, Severity); 14] = [
+    ("json_syntax", Severity::Error),
+    ("schema", Severity::Error),
+    ("extensions", Severity::Error),
+    ("parents_children_consistency", Severity::Error),
+    ("wrong_vertex_index", Severity::Error),
+    ("semantics_arrays", Severity::Error),
+    ("textures", Severity::Error),
+    ("materials", Severity::Error),
+    ("attribute_schemas", Severity::Error),
+    ("profile", Severity::Error),
+    ("extra_root_properties", Severity::Warning),
+    ("duplicate_vertices", Severity::Warning),
+    ("unused_vertices", Severity::Warning),
+    ("extension_versions", Severity::Warning),
+];
+
+/// The default severity of a built-in check
+pub(crate) fn default_severity(check: &str) -> Severity {
+    CHECKS
+        .iter()
+        .find(|(c, _)| *c == check)
+        .map_or(Severity::Error, |(_, s)| *s)
+}
+
+/// The severity of a check: an error makes the file invalid, a warning
+/// doesn't, and a check that is off is not performed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Off,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Severity::Error),
+            "warning" => Ok(Severity::Warning),
+            "off" => Ok(Severity::Off),
+            _ => Err(format!(
+                "unknown severity '{}' (must be \"error\", \"warning\" or \"off\")",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Off => "off",
+        })
+    }
+}
+
+/// The rule set used by [`crate::CJValidator::validate`]: the severity of
+/// each check.
+/// ```rust
+/// use cjval::{CJValidator, Severity, ValidationOptions};
+/// let mut opts = ValidationOptions::new();
+/// opts.set("duplicate_vertices", Severity::Error).unwrap();
+/// opts.set("unused_vertices", Severity::Off).unwrap();
+/// let s1 = std::fs::read_to_string("./data/cube.city.json").unwrap();
+/// let mut v = CJValidator::from_str(&s1);
+/// v.set_options(opts);
+/// let re = v.validate();
+/// assert!(!re["duplicate_vertices"].is_warning());
+/// assert!(re["unused_vertices"].is_disabled());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationOptions {
+    name: String,
+    rules: IndexMap<String, Severity>,
+    exhaustive: bool,
+    error_limit: Option<usize>,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        ValidationOptions {
+            name: "default".to_string(),
+            rules: CHECKS.iter().map(|(c, s)| (c.to_string(), *s)).collect(),
+            exhaustive: false,
+            error_limit: None,
+        }
+    }
+}
+
+/// The content of a config file (eg `cjval.toml`)
+#[cfg(feature = "toml")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    name: Option<String>,
+    exhaustive: Option<bool>,
+    /// 0 for no limit
+    error_limit: Option<usize>,
+    #[serde(default)]
+    rules: std::collections::BTreeMap<String, Severity>,
+}
+
+impl ValidationOptions {
+    /// The default rule set, all the checks are performed
+    pub fn new() -> Self {
+        ValidationOptions::default()
+    }
+
+    /// Reads the rule set from a TOML string (eg the content of `cjval.toml`),
+    /// the checks that are not listed keep their default severity
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(s: &str) -> Result<Self, String> {
+        let mut opts = ValidationOptions::new();
+        opts.update_from_toml_str(s)?;
+        Ok(opts)
+    }
+
+    /// Changes the options with a TOML string, what it doesn't give is kept
+    /// (eg a limit of errors set before)
+    #[cfg(feature = "toml")]
+    pub fn update_from_toml_str(&mut self, s: &str) -> Result<(), String> {
+        let c: ConfigFile = toml::from_str(s).map_err(|e| e.to_string())?;
+        if let Some(name) = c.name {
+            self.set_name(&name);
+        }
+        if let Some(exhaustive) = c.exhaustive {
+            self.set_exhaustive(exhaustive);
+        }
+        if let Some(l) = c.error_limit {
+            self.set_error_limit(Some(l).filter(|l| *l > 0));
+        }
+        for (check, severity) in c.rules {
+            self.set(&check, severity)?;
+        }
+        Ok(())
+    }
+
+    /// The name of the rule set ("default" if not given)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    /// Returns true if all the checks are performed, even after errors
+    pub fn is_exhaustive(&self) -> bool {
+        self.exhaustive
+    }
+
+    /// All the checks are performed even after errors (in the schema, etc.),
+    /// on the parts of the file that can be read
+    pub fn set_exhaustive(&mut self, exhaustive: bool) {
+        self.exhaustive = exhaustive;
+    }
+
+    /// The number of different errors listed for each check (None, the
+    /// default, if all of them are)
+    pub fn error_limit(&self) -> Option<usize> {
+        self.error_limit
+    }
+
+    /// Only the first `limit` different errors of each check are kept, the
+    /// others are counted; None to keep all of them
+    pub fn set_error_limit(&mut self, limit: Option<usize>) {
+        self.error_limit = limit;
+    }
+
+    /// Changes the severity of a check, the built-in ones or those added
+    /// with [`crate::CJValidator::add_check`]. Since the checks can be added
+    /// later, any name is accepted here, but [`crate::CJValidator::validate`]
+    /// reports an error (in *options*) for the names that are not those of a
+    /// check (see [`crate::CJValidator::get_check_names`]). *json_syntax*
+    /// cannot be changed.
+    pub fn set(&mut self, check: &str, severity: Severity) -> Result<(), String> {
+        if check == "json_syntax" {
+            return Err("the severity of 'json_syntax' cannot be changed".to_string());
+        }
+        if check.is_empty() {
+            return Err("the name of the check is empty".to_string());
+        }
+        self.rules.insert(check.to_string(), severity);
+        Ok(())
+    }
+
+    /// The severity of a check
+    pub fn severity(&self, check: &str) -> Severity {
+        self.rules.get(check).copied().unwrap_or(Severity::Error)
+    }
+
+    /// Returns true if the check is performed
+    pub fn is_enabled(&self, check: &str) -> bool {
+        self.severity(check) != Severity::Off
+    }
+
+    /// All the built-in checks and their severity, in the order they are
+    /// performed, then the other checks whose severity was set
+    pub fn rules(&self) -> &IndexMap<String, Severity> {
+        &self.rules
+    }
+
+    /// The checks whose severity is not the default one (all those that
+    /// are not built-in)
+    pub fn changes(&self) -> Vec<(String, Severity)> {
+        self.rules
+            .iter()
+            .filter(|(c, s)| CHECKS.iter().all(|(c2, s2)| c2 != c || s2 != *s))
+            .map(|(c, s)| (c.to_string(), *s))
+            .collect()
+    }
+}
+
+impl fmt::Display for ValidationOptions {
+    /// The name of the rule set and the changes, eg
+    /// "delivery-contract (duplicate_vertices=error, unused_vertices=off)"
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.name)?;
+        let mut l: Vec<String> = self
+            .changes()
+            .iter()
+            .map(|(c, s)| format!("{}={}", c, s))
+            .collect();
+        if self.exhaustive {
+            l.push("exhaustive".to_string());
+        }
+        if let Some(n) = self.error_limit {
+            l.push(format!("error_limit={}", n));
+        }
+        if !l.is_empty() {
+            write!(fmt, " ({})", l.join(", "))?;
+        }
+        Ok(())
+    }
+}
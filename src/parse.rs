@@ -0,0 +1,512 @@
+//! Parsing of a CityJSON/CityJSONFeature in one pass: the document is
+//! converted to a `serde_json::Value`, the duplicate keys in "CityObjects"
+//! are detected at the same time (serde_json keeps silently only the last
+//! one), and the locations in the input of the values are kept.
+//!
+//! The locations kept are those of the members of the objects (the location
+//! of their key) and of the objects in the arrays, eg "/CityObjects/id-1",
+//! "/CityObjects/id-1/geometry/0" and "/CityObjects/id-1/geometry/0/lod"; not
+//! those of the arrays in arrays (eg the vertices or the boundaries), there
+//! would be millions of them.
+//!
+//! Keeping the locations is optional: for a large file they take a lot of
+//! memory (a node for each of them).
+
+use serde::de::value::SeqAccessDeserializer;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::io::Read;
+use std::sync::OnceLock;
+
+/// A location in the input (a file, or a line of a CityJSONSeq)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The byte offset (from 0)
+    pub offset: usize,
+    /// The line (from 1), for a CityJSONSeq it is the line of the feature
+    pub line: usize,
+    /// The column in characters (from 1)
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// The locations of the values, their JSON Pointers are kept as a tree: each
+/// value is a segment (a key or an index) of its parent, and the keys are
+/// stored once (there are millions of values in large files)
+#[derive(Debug)]
+pub(crate) struct Locations {
+    keys: HashMap<Box<str>, u32>,
+    /// By node, the root is 0
+    nodes: Vec<Node>,
+    /// (parent, segment) -> node, built when the 1st location is looked up
+    children: OnceLock<HashMap<(u32, Segment), u32, BuildHasherDefault<FxHasher>>>,
+}
+
+#[derive(Debug)]
+struct Node {
+    parent: u32,
+    segment: Segment,
+    /// None if it is not known
+    location: Option<Location>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Segment {
+    Key(u32),
+    Index(u32),
+}
+
+impl Default for Locations {
+    fn default() -> Self {
+        Locations {
+            keys: HashMap::new(),
+            nodes: vec![Node {
+                parent: 0,
+                segment: Segment::Index(0),
+                location: None,
+            }],
+            children: OnceLock::new(),
+        }
+    }
+}
+
+impl Locations {
+    fn key(&mut self, k: &str) -> Segment {
+        if let Some(i) = self.keys.get(k) {
+            return Segment::Key(*i);
+        }
+        let i = self.keys.len() as u32;
+        self.keys.insert(k.into(), i);
+        Segment::Key(i)
+    }
+
+    fn add(&mut self, parent: u32, segment: Segment, location: Option<Location>) -> u32 {
+        self.nodes.push(Node {
+            parent,
+            segment,
+            location,
+        });
+        (self.nodes.len() - 1) as u32
+    }
+
+    /// The location of the longest part of the JSON Pointer that is known, eg
+    /// that of "/CityObjects/id-1/geometry/0" for
+    /// "/CityObjects/id-1/geometry/0/boundaries/2/0"
+    pub(crate) fn locate(&self, pointer: &str) -> Option<Location> {
+        let rest = pointer.strip_prefix('/')?;
+        let children = self.children.get_or_init(|| {
+            let mut m = HashMap::default();
+            for (i, n) in self.nodes.iter().enumerate().skip(1) {
+                //-- the last one of duplicate keys, like serde_json
+                m.insert((n.parent, n.segment), i as u32);
+            }
+            m
+        });
+        let mut node = 0;
+        let mut found = None;
+        for segment in rest.split('/') {
+            let k = segment.replace("~1", "/").replace("~0", "~");
+            let child = self
+                .keys
+                .get(k.as_str())
+                .and_then(|i| children.get(&(node, Segment::Key(*i))))
+                .or_else(|| {
+                    let i = segment.parse::<u32>().ok()?;
+                    children.get(&(node, Segment::Index(i)))
+                });
+            let Some(child) = child else {
+                break;
+            };
+            node = *child;
+            found = self.nodes[node as usize].location.or(found);
+        }
+        found
+    }
+
+    /// Sets the lines and the columns of the locations (only the offsets are
+    /// known when a string is parsed), they are in the order of the input
+    fn set_lines(&mut self, s: &str) {
+        let (mut pos, mut line, mut column) = (0, 1, 1);
+        for l in self.nodes.iter_mut().filter_map(|n| n.location.as_mut()) {
+            if l.offset < pos {
+                (pos, line, column) = (0, 1, 1);
+            }
+            let part = &s.as_bytes()[pos..l.offset];
+            match part.iter().rposition(|b| *b == b'\n') {
+                Some(i) => {
+                    line += part.iter().filter(|b| **b == b'\n').count();
+                    column = s[pos + i + 1..l.offset].chars().count() + 1;
+                }
+                None => column += s[pos..l.offset].chars().count(),
+            }
+            l.line = line;
+            l.column = column;
+            pos = l.offset;
+        }
+    }
+}
+
+/// The hash function of rustc (FxHash), much faster than the default one for
+/// the nodes of [`Locations`] (they are numbers, not given by the input)
+#[derive(Default)]
+struct FxHasher(u64);
+
+impl FxHasher {
+    fn add(&mut self, x: u64) {
+        self.0 = (self.0.rotate_left(5) ^ x).wrapping_mul(0x517cc1b727220a95);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for c in &mut chunks {
+            self.add(u64::from_le_bytes(c.try_into().unwrap()));
+        }
+        for b in chunks.remainder() {
+            self.add(*b as u64);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Result of the parsing: the JSON, whether "CityObjects" has duplicate keys,
+/// and the locations of the values
+pub(crate) struct Parsed {
+    pub j: Value,
+    pub duplicate_keys: bool,
+    pub locations: Locations,
+}
+
+/// A string is parsed without copying it (the strings without escapes are
+/// borrowed), the locations (if they are kept) are found with the offsets
+/// of the keys
+pub(crate) fn parse_str(s: &str, locations: bool) -> Result<Parsed, serde_json::Error> {
+    let mut de = serde_json::Deserializer::from_str(s);
+    if !locations {
+        return parse(&mut de, Source::None);
+    }
+    let mut p = parse(&mut de, Source::Str(s))?;
+    p.locations.set_lines(s);
+    Ok(p)
+}
+
+pub(crate) fn parse_reader<R: Read>(r: R, locations: bool) -> Result<Parsed, serde_json::Error> {
+    if !locations {
+        let r = std::io::BufReader::with_capacity(64 * 1024, r);
+        return parse(&mut serde_json::Deserializer::from_reader(r), Source::None);
+    }
+    let last = Cell::new(Location {
+        offset: 0,
+        line: 1,
+        column: 1,
+    });
+    let mut de = serde_json::Deserializer::from_reader(Counter {
+        inner: r,
+        buf: vec![0; 64 * 1024],
+        pos: 0,
+        len: 0,
+        next: last.get(),
+        last: &last,
+    });
+    parse(&mut de, Source::Reader(&last))
+}
+
+fn parse<'de, R: serde_json::de::Read<'de>>(
+    de: &mut serde_json::Deserializer<R>,
+    source: Source,
+) -> Result<Parsed, serde_json::Error> {
+    let mut state = State {
+        source,
+        duplicate_keys: false,
+        locations: Locations::default(),
+    };
+    let j = LocSeed {
+        state: &mut state,
+        node: 0,
+        index: None,
+        kind: Kind::Root,
+    }
+    .deserialize(&mut *de)?;
+    de.end()?;
+    Ok(Parsed {
+        j,
+        duplicate_keys: state.duplicate_keys,
+        locations: state.locations,
+    })
+}
+
+/// The key as a segment of a JSON Pointer ("~" -> "~0" and "/" -> "~1")
+pub(crate) fn escape(key: &str) -> Cow<'_, str> {
+    if key.contains(['~', '/']) {
+        Cow::Owned(key.replace('~', "~0").replace('/', "~1"))
+    } else {
+        Cow::Borrowed(key)
+    }
+}
+
+/// A buffered reader that keeps the location of the last byte read:
+/// serde_json reads (and peeks) one byte at a time, when a value is visited
+/// the last byte read is its first one ('{' or '"' for a key)
+struct Counter<'a, R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+    next: Location,
+    last: &'a Cell<Location>,
+}
+
+impl<'a, R: Read> Read for Counter<'a, R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos == self.len {
+            self.len = self.inner.read(&mut self.buf)?;
+            self.pos = 0;
+        }
+        let n = out.len().min(self.len - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        for b in &out[..n] {
+            //-- the continuation bytes of a UTF-8 character
+            if b & 0xC0 == 0x80 {
+                self.next.offset += 1;
+                continue;
+            }
+            self.last.set(self.next);
+            self.next.offset += 1;
+            if *b == b'\n' {
+                self.next.line += 1;
+                self.next.column = 1;
+            } else {
+                self.next.column += 1;
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// Where the locations come from: the reader keeps the location of the last
+/// byte read, in a string it is the offset of the keys; None if they are not
+/// kept
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Reader(&'a Cell<Location>),
+    Str(&'a str),
+    None,
+}
+
+struct State<'a> {
+    source: Source<'a>,
+    duplicate_keys: bool,
+    locations: Locations,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Root,
+    CityObjects,
+    Other,
+}
+
+/// A value, `node` is its node in the [`Locations`] (added by the parent
+/// for the members of objects) or that of its array if it is the element
+/// `index`
+struct LocSeed<'a, 's> {
+    state: &'a mut State<'s>,
+    node: u32,
+    index: Option<usize>,
+    kind: Kind,
+}
+
+impl<'de, 'a, 's> DeserializeSeed<'de> for LocSeed<'a, 's> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a, 's> Visitor<'de> for LocSeed<'a, 's> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any valid JSON value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let source = self.state.source;
+        let locations = &mut self.state.locations;
+        //-- an object in an array, in a string its '{' is found with its 1st key
+        let node = match (self.index, source) {
+            (None, _) | (_, Source::None) => self.node,
+            (Some(i), Source::Reader(last)) => {
+                locations.add(self.node, Segment::Index(i as u32), Some(last.get()))
+            }
+            (Some(i), Source::Str(_)) => locations.add(self.node, Segment::Index(i as u32), None),
+        };
+        let mut first = true;
+        let mut m = Map::new();
+        while let Some((k, loc)) = access.next_key_seed(KeySeed(source))? {
+            let locations = &mut self.state.locations;
+            if let (true, Some(_), Source::Str(s), Some(l)) = (first, self.index, source, loc) {
+                let before = s[..l.offset].trim_end_matches([' ', '\t', '\n', '\r']);
+                if before.ends_with('{') {
+                    let offset = before.len() - 1;
+                    locations.nodes[node as usize].location = Some(Location { offset, ..l });
+                }
+            }
+            first = false;
+            let child = match source {
+                Source::None => node,
+                _ => {
+                    let segment = locations.key(&k);
+                    locations.add(node, segment, loc)
+                }
+            };
+            let v = access.next_value_seed(LocSeed {
+                state: &mut *self.state,
+                node: child,
+                index: None,
+                kind: if self.kind == Kind::Root && k == "CityObjects" {
+                    Kind::CityObjects
+                } else {
+                    Kind::Other
+                },
+            })?;
+            if self.kind == Kind::CityObjects && m.contains_key(&k) {
+                self.state.duplicate_keys = true;
+            }
+            m.insert(k, v);
+        }
+        Ok(Value::Object(m))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        //-- the arrays in arrays (eg the boundaries) are only parsed
+        if self.index.is_some() {
+            return Value::deserialize(SeqAccessDeserializer::new(access));
+        }
+        let mut a = Vec::new();
+        while let Some(v) = access.next_element_seed(LocSeed {
+            state: &mut *self.state,
+            node: self.node,
+            index: Some(a.len()),
+            kind: Kind::Other,
+        })? {
+            a.push(v);
+        }
+        Ok(Value::Array(a))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+}
+
+/// A key of an object, with its location (that of its opening '"'); in a
+/// string it is only known if the key is borrowed (it has no escapes), and
+/// the line and the column are set after the parsing
+struct KeySeed<'a>(Source<'a>);
+
+impl<'de, 'a> DeserializeSeed<'de> for KeySeed<'a> {
+    type Value = (String, Option<Location>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        match self.0 {
+            Source::Reader(last) => {
+                let loc = last.get();
+                Ok((String::deserialize(deserializer)?, Some(loc)))
+            }
+            Source::Str(s) => deserializer.deserialize_str(KeyVisitor(s)),
+            Source::None => Ok((String::deserialize(deserializer)?, None)),
+        }
+    }
+}
+
+struct KeyVisitor<'a>(&'a str);
+
+impl<'de, 'a> Visitor<'de> for KeyVisitor<'a> {
+    type Value = (String, Option<Location>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E> {
+        let offset = (v.as_ptr() as usize)
+            .checked_sub(self.0.as_ptr() as usize + 1)
+            .filter(|o| *o < self.0.len());
+        let loc = offset.map(|offset| Location {
+            offset,
+            line: 0,
+            column: 0,
+        });
+        Ok((v.to_string(), loc))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok((v.to_string(), None))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok((v, None))
+    }
+}
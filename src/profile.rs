@@ -0,0 +1,291 @@
+//! Validation profiles: data-quality requirements that go beyond the CityJSON
+//! specifications (eg every Building must have an LoD2.2 Solid), reported
+//! as the check *profile*.
+//!
+//! A profile is written in JSON (or in TOML with the cargo feature `toml`):
+//!
+//! ```json
+//! {
+//!   "name": "3D BAG delivery",
+//!   "properties": {
+//!     "/metadata/referenceSystem": {
+//!       "required": true,
+//!       "allowed": ["https://www.opengis.net/def/crs/EPSG/0/7415"]
+//!     }
+//!   },
+//!   "cityobjects": {
+//!     "Building": {
+//!       "geometry": [{"type": "Solid", "lod": "2.2"}],
+//!       "attributes": {
+//!         "identificatie": {"required": true, "type": "string", "unique": true},
+//!         "status": {"allowed": ["Pand in gebruik", "Bouw gestart"]}
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! The root properties are given with a JSON Pointer, and are not checked for
+//! a CityJSONFeature (they are in the 1st line of a CityJSONSeq). The
+//! uniqueness is checked among the CityObjects of one file, or of all the
+//! features of a CityJSONSeq (those added before to the validator).
+
+use crate::check::ErrorList;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// A validation profile, see the module documentation for the format
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub name: Option<String>,
+    /// The root properties, with a JSON Pointer (eg "/metadata/referenceSystem")
+    #[serde(default)]
+    pub properties: BTreeMap<String, ValueRule>,
+    /// The rules per CityObject type (eg "Building")
+    #[serde(default)]
+    pub cityobjects: BTreeMap<String, CityObjectRules>,
+}
+
+/// The rules for one CityObject type
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CityObjectRules {
+    /// Each one must be matched by at least one geometry of the CityObject
+    #[serde(default)]
+    pub geometry: Vec<GeometryRule>,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, ValueRule>,
+}
+
+/// A required geometry: its type and/or its LoD
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeometryRule {
+    #[serde(rename = "type")]
+    pub geom_type: Option<String>,
+    /// eg "2.2" (a string, also for the LoDs that are numbers in v1.0)
+    pub lod: Option<String>,
+}
+
+/// The rules for an attribute or a root property
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValueRule {
+    #[serde(default)]
+    pub required: bool,
+    #[serde(rename = "type")]
+    pub value_type: Option<ValueType>,
+    /// The value must be unique among the CityObjects of that type
+    /// (attributes only)
+    #[serde(default)]
+    pub unique: bool,
+    /// The allowed values
+    pub allowed: Option<Vec<Value>>,
+}
+
+/// The JSON type of a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+    Null,
+}
+
+impl ValueType {
+    fn matches(&self, v: &Value) -> bool {
+        match self {
+            ValueType::String => v.is_string(),
+            ValueType::Number => v.is_number(),
+            ValueType::Integer => v.is_i64() || v.is_u64(),
+            ValueType::Boolean => v.is_boolean(),
+            ValueType::Array => v.is_array(),
+            ValueType::Object => v.is_object(),
+            ValueType::Null => v.is_null(),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            ValueType::String => "a string",
+            ValueType::Number => "a number",
+            ValueType::Integer => "an integer",
+            ValueType::Boolean => "a boolean",
+            ValueType::Array => "an array",
+            ValueType::Object => "an object",
+            ValueType::Null => "null",
+        }
+    }
+}
+
+impl Profile {
+    /// Reads a profile from a JSON string
+    /// ```rust
+    /// let s = r#"{"cityobjects": {"Building": {"geometry": [{"lod": "2.2"}]}}}"#;
+    /// let p = cjval::Profile::from_json_str(s).unwrap();
+    /// assert_eq!(p.cityobjects["Building"].geometry[0].lod.as_deref(), Some("2.2"));
+    /// ```
+    pub fn from_json_str(s: &str) -> Result<Self, String> {
+        serde_json::from_str(s).map_err(|e| e.to_string())
+    }
+
+    /// Reads a profile from a TOML string
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(s: &str) -> Result<Self, String> {
+        toml::from_str(s).map_err(|e| e.to_string())
+    }
+
+    /// The name of the profile ("profile" if not given)
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("profile")
+    }
+}
+
+/// The values of the "unique" attributes of the previous features of a
+/// CityJSONSeq: (CityObject type, attribute) => value => id of the CityObject
+pub(crate) type UniqueValues = HashMap<(String, String), HashMap<String, String>>;
+
+/// Keeps the values of the "unique" attributes of the CityObjects, for the
+/// next features
+pub(crate) fn collect_unique_values(profile: &Profile, j: &Value, seen: &mut UniqueValues) {
+    for (id, co) in j["CityObjects"].as_object().into_iter().flatten() {
+        let Some(cotype) = co["type"].as_str() else {
+            continue;
+        };
+        let Some(rules) = profile.cityobjects.get(cotype) else {
+            continue;
+        };
+        for (att, _) in rules.attributes.iter().filter(|(_, r)| r.unique) {
+            if let Some(v) = co["attributes"].get(att) {
+                seen.entry((cotype.to_string(), att.to_string()))
+                    .or_default()
+                    .entry(v.to_string())
+                    .or_insert_with(|| id.to_string());
+            }
+        }
+    }
+}
+
+/// Validates the CityJSON (or a CityJSONFeature if `is_cjfeature`) against
+/// the profile, the values of the "unique" attributes must also not be in
+/// `seen`
+pub(crate) fn validate_profile(
+    profile: &Profile,
+    j: &Value,
+    is_cjfeature: bool,
+    seen: &UniqueValues,
+    ls_errors: &mut ErrorList,
+) {
+    //-- root properties
+    if !is_cjfeature {
+        for (pointer, rule) in &profile.properties {
+            let what = format!("Property '{}'", pointer);
+            if let Some(e) = check_value(rule, j.pointer(pointer), &what) {
+                ls_errors.push(e);
+            }
+        }
+    }
+    //-- CityObjects
+    let Some(cos) = j["CityObjects"].as_object() else {
+        return;
+    };
+    //-- (CityObject type, attribute) => value => id of the 1st CityObject
+    let mut uniques: HashMap<(&str, &str), HashMap<String, &str>> = HashMap::new();
+    for (id, co) in cos {
+        let Some(cotype) = co["type"].as_str() else {
+            continue;
+        };
+        let Some(rules) = profile.cityobjects.get(cotype) else {
+            continue;
+        };
+        for gr in &rules.geometry {
+            let found = co["geometry"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|g| gr.matches(g));
+            if !found {
+                ls_errors.push_in(
+                    id,
+                    format!(
+                        "CityObject #{} ({}) has no geometry{}",
+                        id,
+                        cotype,
+                        gr.description()
+                    ),
+                );
+            }
+        }
+        for (att, rule) in &rules.attributes {
+            let v = co["attributes"].get(att);
+            let what = format!("Attribute '{}' of CityObject #{} ({})", att, id, cotype);
+            if let Some(e) = check_value(rule, v, &what) {
+                ls_errors.push_in(id, e);
+            }
+            if let Some(v) = v.filter(|_| rule.unique) {
+                let sv = v.to_string();
+                let before = seen
+                    .get(&(cotype.to_string(), att.to_string()))
+                    .and_then(|m| m.get(&sv));
+                let here = uniques.entry((cotype, att)).or_default();
+                if let Some(other) = before.map(String::as_str).or(here.get(&sv).copied()) {
+                    ls_errors.push_in(
+                        id,
+                        format!("{} is not unique ({} is also used by #{})", what, v, other),
+                    );
+                } else {
+                    here.insert(sv, id);
+                }
+            }
+        }
+    }
+}
+
+/// The error of a value that doesn't follow its rule, if there is one
+fn check_value(rule: &ValueRule, v: Option<&Value>, what: &str) -> Option<String> {
+    let Some(v) = v else {
+        return Some(format!("{} is missing", what)).filter(|_| rule.required);
+    };
+    if let Some(t) = rule.value_type {
+        if !t.matches(v) {
+            return Some(format!("{} is not {} ({})", what, t.name(), v));
+        }
+    }
+    match &rule.allowed {
+        Some(allowed) if !allowed.contains(v) => {
+            Some(format!("{} has a value that is not allowed ({})", what, v))
+        }
+        _ => None,
+    }
+}
+
+impl GeometryRule {
+    fn matches(&self, g: &Value) -> bool {
+        let lod = match &g["lod"] {
+            Value::String(s) => s.to_string(),
+            x => x.to_string(),
+        };
+        self.geom_type
+            .as_ref()
+            .map_or(true, |t| g["type"] == t.as_str())
+            && self.lod.as_ref().map_or(true, |l| *l == lod)
+    }
+
+    /// eg " of type 'Solid' with LoD 2.2"
+    fn description(&self) -> String {
+        let mut s = String::new();
+        if let Some(t) = &self.geom_type {
+            s += &format!(" of type '{}'", t);
+        }
+        if let Some(l) = &self.lod {
+            s += &format!(" with LoD {}", l);
+        }
+        s
+    }
+}
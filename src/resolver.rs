@@ -0,0 +1,313 @@
+//! Resolution of the Extensions listed in the "extensions" property of a
+//! CityJSON file.
+//!
+//! An [`ExtensionResolver`] returns the content of the schema (the `.ext.json`
+//! file) of an Extension from its name and URL. Several resolvers are
+//! provided: from a local directory, from memory, from the web (with the
+//! cargo feature `http`), and a cache on disk wrapping any other resolver.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// An Extension as declared in the "extensions" property of a CityJSON file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionRef {
+    /// The name (the key in "extensions"), eg "Noise"
+    pub name: String,
+    /// The URL of the schema, eg "https://someurl.org/noise.ext.json"
+    pub url: String,
+    /// The version, eg "1.0"
+    pub version: String,
+}
+
+impl ExtensionRef {
+    /// The name of the schema file, the last segment of the URL
+    /// (eg "noise.ext.json")
+    pub fn file_name(&self) -> &str {
+        crate::schemaset::file_name(&self.url)
+    }
+
+    /// Returns true if the version of an Extension file is the declared one,
+    /// only the components that are declared are compared
+    /// (eg "1.0" matches "1.0.2" but not "1.1.0")
+    pub fn matches_version(&self, actual: &str) -> bool {
+        let mut a = actual.trim().split('.');
+        self.version
+            .trim()
+            .split('.')
+            .all(|d| d == a.next().unwrap_or("0"))
+    }
+}
+
+/// Returns the content (a JSON string) of the schema of an Extension
+pub trait ExtensionResolver {
+    fn resolve(&self, ext: &ExtensionRef) -> Result<String>;
+}
+
+/// Status of the resolution of one Extension, see
+/// [`crate::CJValidator::resolve_extensions`]
+#[derive(Debug, Clone)]
+pub struct ExtensionStatus {
+    pub url: String,
+    /// The version declared in "extensions"
+    pub version: String,
+    /// The version in the Extension file (if it was resolved)
+    pub ext_version: Option<String>,
+    /// None if the Extension was resolved and added to the validator
+    pub error: Option<String>,
+}
+
+impl ExtensionStatus {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Reads the Extensions from a local directory: the file is
+/// "{name}/{version}/" followed by the last segment of the URL
+/// (eg "Noise/1.0/noise.ext.json"), or else directly the last segment of the URL
+/// (eg "noise.ext.json"), or else "{name}.ext.json".
+///
+/// The name, the version and the URL come from the CityJSON file: if the name
+/// or the version is not a plain file name (it contains a path separator, is
+/// ".." or is absolute) the Extension is not resolved, and the last segment
+/// of the URL is only used if it is one, so that no file outside the
+/// directory can be read
+#[derive(Debug, Clone)]
+pub struct LocalDirResolver {
+    dir: PathBuf,
+}
+
+impl LocalDirResolver {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        LocalDirResolver {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl ExtensionResolver for LocalDirResolver {
+    fn resolve(&self, ext: &ExtensionRef) -> Result<String> {
+        for (what, s) in [("name", &ext.name), ("version", &ext.version)] {
+            if !is_file_name(s) {
+                return Err(anyhow!(
+                    "Extension {}: invalid {} \"{}\", it must be a file name",
+                    ext.name,
+                    what,
+                    s
+                ));
+            }
+        }
+        let mut candidates = Vec::new();
+        if is_file_name(ext.file_name()) {
+            candidates.push(
+                self.dir
+                    .join(&ext.name)
+                    .join(&ext.version)
+                    .join(ext.file_name()),
+            );
+            candidates.push(self.dir.join(ext.file_name()));
+        }
+        candidates.push(self.dir.join(format!("{}.ext.json", ext.name)));
+        for p in candidates.iter() {
+            if p.is_file() {
+                return Ok(std::fs::read_to_string(p)?);
+            }
+        }
+        Err(anyhow!(
+            "Extension file for {} not found in {}",
+            ext.name,
+            self.dir.display()
+        ))
+    }
+}
+
+/// Returns true if s is one normal component of a path (not "", "." or "..",
+/// without separator, and not absolute)
+fn is_file_name(s: &str) -> bool {
+    let mut cs = Path::new(s).components();
+    matches!(
+        (cs.next(), cs.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    ) && !s.contains(['/', '\\'])
+}
+
+/// Extensions kept in memory, found either by their URL or their name; one
+/// version in particular can be added with [`MemoryResolver::insert_version`]
+/// ```rust
+/// use cjval::{CJValidator, MemoryResolver};
+/// let sdata = std::fs::read_to_string("./data/noise1.city.json").unwrap();
+/// let sext = std::fs::read_to_string("./schemas/extensions/11/noise.ext.json").unwrap();
+/// let mut resolver = MemoryResolver::new();
+/// resolver.insert("Noise", &sext);
+/// let mut val = CJValidator::from_str(&sdata);
+/// let statuses = val.resolve_extensions(&resolver);
+/// assert!(statuses["Noise"].is_ok());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    exts: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        MemoryResolver::default()
+    }
+
+    /// Adds the content of an Extension, the key is its name or its URL
+    pub fn insert(&mut self, key: &str, content: &str) {
+        self.exts.insert(key.to_string(), content.to_string());
+    }
+
+    /// Adds the content of one version of an Extension, it is used instead of
+    /// the one added with [`MemoryResolver::insert`] when that version is declared
+    pub fn insert_version(&mut self, key: &str, version: &str, content: &str) {
+        self.exts
+            .insert(format!("{}@{}", key, version), content.to_string());
+    }
+}
+
+impl ExtensionResolver for MemoryResolver {
+    fn resolve(&self, ext: &ExtensionRef) -> Result<String> {
+        self.exts
+            .get(&format!("{}@{}", ext.url, ext.version))
+            .or_else(|| self.exts.get(&format!("{}@{}", ext.name, ext.version)))
+            .or_else(|| self.exts.get(&ext.url))
+            .or_else(|| self.exts.get(&ext.name))
+            .cloned()
+            .ok_or_else(|| anyhow!("Extension {} not found ({})", ext.name, ext.url))
+    }
+}
+
+/// Keeps on disk a copy of the Extensions resolved by another resolver
+/// (eg [`HttpResolver`]), the next resolutions read the copy.
+///
+/// By default a copy never expires; with [`CacheResolver::set_max_age`] a
+/// copy older than that (its modification time) is resolved again, and is
+/// only used if the other resolver fails (eg offline). The copy of one
+/// Extension is deleted with [`CacheResolver::remove`].
+#[derive(Debug, Clone)]
+pub struct CacheResolver<R: ExtensionResolver> {
+    dir: PathBuf,
+    inner: R,
+    max_age: Option<Duration>,
+}
+
+impl<R: ExtensionResolver> CacheResolver<R> {
+    pub fn new<P: AsRef<Path>>(dir: P, inner: R) -> Self {
+        CacheResolver {
+            dir: dir.as_ref().to_path_buf(),
+            inner,
+            max_age: None,
+        }
+    }
+
+    /// The copies older than `max_age` are resolved again (eg a newer
+    /// Extension published with the same URL and version); None (the
+    /// default) for copies that never expire
+    pub fn set_max_age(&mut self, max_age: Option<Duration>) {
+        self.max_age = max_age;
+    }
+
+    /// Deletes the copy of an Extension, it is resolved again the next time
+    pub fn remove(&self, ext: &ExtensionRef) -> std::io::Result<()> {
+        match std::fs::remove_file(self.cache_path(ext)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_fresh(&self, p: &Path) -> bool {
+        match self.max_age {
+            None => true,
+            Some(max_age) => std::fs::metadata(p)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.elapsed().ok())
+                .is_some_and(|age| age < max_age),
+        }
+    }
+
+    fn cache_path(&self, ext: &ExtensionRef) -> PathBuf {
+        let key: String = format!("{}_{}", ext.url, ext.version)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(key)
+    }
+}
+
+impl<R: ExtensionResolver> ExtensionResolver for CacheResolver<R> {
+    fn resolve(&self, ext: &ExtensionRef) -> Result<String> {
+        let p = self.cache_path(ext);
+        let cached = std::fs::read_to_string(&p).ok();
+        if let Some(s) = &cached {
+            if self.is_fresh(&p) {
+                return Ok(s.to_string());
+            }
+        }
+        let s = match (self.inner.resolve(ext), cached) {
+            (Ok(s), _) => s,
+            //-- an expired copy is better than nothing
+            (Err(_), Some(s)) => return Ok(s),
+            (Err(e), None) => return Err(e),
+        };
+        //-- a cache that cannot be written is not an error
+        if std::fs::create_dir_all(&self.dir).is_ok() {
+            let _ = std::fs::write(&p, &s);
+        }
+        Ok(s)
+    }
+}
+
+/// Downloads the Extensions from their URL (cargo feature `http`), a
+/// download fails after 30s (see [`HttpResolver::with_timeout`])
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct HttpResolver {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "http")]
+impl HttpResolver {
+    pub fn new() -> Self {
+        HttpResolver::with_timeout(std::time::Duration::from_secs(30))
+    }
+
+    /// An HttpResolver whose downloads fail after `timeout` (to connect and
+    /// to read the Extension)
+    pub fn with_timeout(timeout: std::time::Duration) -> Self {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Cannot build the HTTP client");
+        HttpResolver { client }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Default for HttpResolver {
+    fn default() -> Self {
+        HttpResolver::new()
+    }
+}
+
+#[cfg(feature = "http")]
+impl ExtensionResolver for HttpResolver {
+    fn resolve(&self, ext: &ExtensionRef) -> Result<String> {
+        let res = self.client.get(&ext.url).send()?;
+        if res.status().is_success() {
+            Ok(res.text()?)
+        } else {
+            Err(anyhow!("Cannot download extension schema: {}", ext.url))
+        }
+    }
+}
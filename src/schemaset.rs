@@ -0,0 +1,425 @@
+//! Compiled CityJSON schemas, shared between validators.
+//!
+//! Compiling the CityJSON schemas is by far the most expensive step of a
+//! validation, a [`SchemaSet`] compiles them once (lazily, per CityJSON
+//! version) and keeps the compiled schemas of the Extensions in a cache.
+//! It can be shared between threads, and between several [`crate::CJValidator`],
+//! eg when validating the many CityJSONFeature of a CityJSONSeq stream.
+//!
+//! The compiled Extension schemas are found in the cache with a key computed
+//! from the Extensions loaded in the validator (not from the schemas). The
+//! cache is emptied when it has more than 1000 schemas, so that it doesn't
+//! grow forever in a long-running process.
+
+use jsonschema::{Draft, JSONSchema};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+static GLOBAL_SCHEMASET: OnceLock<Arc<SchemaSet>> = OnceLock::new();
+
+/// The parsed schemas of CityJSON that an Extension can reference (v1.0,
+/// v1.1 and v2.0), see [`core_values`]
+static CORE_VALUES: [OnceLock<Vec<(&str, Value)>>; 3] =
+    [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+
+/// The maximum number of compiled Extension schemas in the cache
+const EXTENSION_CACHE_MAX: usize = 1000;
+
+/// The compiled schemas of one CityJSON version
+#[derive(Debug)]
+struct VersionSchemas {
+    cityjson: JSONSchema,
+    cityjsonfeature: Option<JSONSchema>,
+    version_schema: String,
+}
+
+/// A set of compiled CityJSON schemas (v1.0, v1.1, v2.0) and a cache of the
+/// compiled Extension schemas.
+/// ```rust
+/// use cjval::{CJValidator, SchemaSet};
+/// use std::sync::Arc;
+/// let schemas = Arc::new(SchemaSet::new());
+/// let s1 = std::fs::read_to_string("./data/cube.city.json")
+///         .expect("Couldn't read CityJSON file");
+/// let v = CJValidator::from_str_with_schemas(&s1, schemas.clone());
+/// let re = v.validate();
+/// ```
+#[derive(Debug, Default)]
+pub struct SchemaSet {
+    v10: OnceLock<VersionSchemas>,
+    v11: OnceLock<VersionSchemas>,
+    v20: OnceLock<VersionSchemas>,
+    extensions: RwLock<HashMap<u64, Arc<JSONSchema>>>,
+}
+
+impl SchemaSet {
+    /// Creates an empty SchemaSet, the schemas are compiled the first time
+    /// they are needed.
+    pub fn new() -> Self {
+        SchemaSet::default()
+    }
+
+    /// The SchemaSet shared by all the validators created with
+    /// [`crate::CJValidator::from_str`].
+    pub fn global() -> Arc<SchemaSet> {
+        GLOBAL_SCHEMASET
+            .get_or_init(|| Arc::new(SchemaSet::new()))
+            .clone()
+    }
+
+    /// Compiles right away the schemas of all the CityJSON versions
+    /// (instead of at the first validation).
+    pub fn precompile(&self) {
+        for v in [10, 11, 20] {
+            self.get(v);
+        }
+    }
+
+    /// Number of compiled Extension schemas currently in the cache.
+    pub fn extension_cache_len(&self) -> usize {
+        self.extensions.read().unwrap().len()
+    }
+
+    fn get(&self, version_file: i32) -> Option<&VersionSchemas> {
+        match version_file {
+            10 => Some(self.v10.get_or_init(|| {
+                compile_version(include_str!("../schemas/10/cityjson.min.schema.json"), None)
+            })),
+            11 => Some(self.v11.get_or_init(|| {
+                compile_version(
+                    include_str!("../schemas/11/cityjson.min.schema.json"),
+                    Some(include_str!(
+                        "../schemas/11/cityjsonfeature.min.schema.json"
+                    )),
+                )
+            })),
+            20 => Some(self.v20.get_or_init(|| {
+                compile_version(
+                    include_str!("../schemas/20/cityjson.min.schema.json"),
+                    Some(include_str!(
+                        "../schemas/20/cityjsonfeature.min.schema.json"
+                    )),
+                )
+            })),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn cityjson(&self, version_file: i32) -> Option<&JSONSchema> {
+        self.get(version_file).map(|vs| &vs.cityjson)
+    }
+
+    pub(crate) fn cityjsonfeature(&self, version_file: i32) -> Option<&JSONSchema> {
+        self.get(version_file)
+            .and_then(|vs| vs.cityjsonfeature.as_ref())
+    }
+
+    pub(crate) fn version_schema(&self, version_file: i32) -> Option<String> {
+        self.get(version_file).map(|vs| vs.version_schema.clone())
+    }
+
+    /// Returns the compiled schema for one property of an Extension, `key`
+    /// identifies it (the Extensions loaded and the property). The schema is
+    /// compiled with `compile` only the first time, then taken from the cache.
+    pub(crate) fn extension(
+        &self,
+        key: u64,
+        compile: impl FnOnce() -> Result<JSONSchema, String>,
+    ) -> Result<Arc<JSONSchema>, String> {
+        if let Some(c) = self.extensions.read().unwrap().get(&key) {
+            return Ok(c.clone());
+        }
+        let compiled = Arc::new(compile()?);
+        let mut cache = self.extensions.write().unwrap();
+        if cache.len() >= EXTENSION_CACHE_MAX {
+            cache.clear();
+        }
+        cache.insert(key, compiled.clone());
+        Ok(compiled)
+    }
+}
+
+fn compile_version(schema_str: &str, schemaf_str: Option<&str>) -> VersionSchemas {
+    let schema: Value = serde_json::from_str(schema_str).unwrap();
+    let version_schema = match schema["$id"].as_str() {
+        //-- "https://www.cityjson.org/schemas/2.0.1/cityjson.min.schema.json"
+        Some(id) => id.get(33..38).unwrap_or("-1").to_string(),
+        None => crate::CITYJSON_V10_VERSION.to_string(),
+    };
+    let cityjson = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(&schema)
+        .expect("A valid schema");
+    let cityjsonfeature = schemaf_str.map(|s| {
+        let schemaf: Value = serde_json::from_str(s).unwrap();
+        JSONSchema::options()
+            .with_draft(Draft::Draft7)
+            .compile(&schemaf)
+            .expect("A valid schema")
+    });
+    VersionSchemas {
+        cityjson,
+        cityjsonfeature,
+        version_schema,
+    }
+}
+
+/// The base URL and the schemas of CityJSON that an Extension can reference
+pub(crate) fn core_documents(
+    version_file: i32,
+) -> Option<(&'static str, [(&'static str, &'static str); 4])> {
+    match version_file {
+        10 => Some((
+            "https://www.cityjson.org/schemas/1.0.3",
+            [
+                (
+                    "cityobjects.schema.json",
+                    include_str!("../schemas/10/cityobjects.schema.json"),
+                ),
+                (
+                    "geomprimitives.schema.json",
+                    include_str!("../schemas/10/geomprimitives.schema.json"),
+                ),
+                (
+                    "appearance.schema.json",
+                    include_str!("../schemas/10/appearance.schema.json"),
+                ),
+                (
+                    "geomtemplates.schema.json",
+                    include_str!("../schemas/10/geomtemplates.schema.json"),
+                ),
+            ],
+        )),
+        11 => Some((
+            "https://www.cityjson.org/schemas/1.1.0",
+            [
+                (
+                    "cityobjects.schema.json",
+                    include_str!("../schemas/11/cityobjects.schema.json"),
+                ),
+                (
+                    "geomprimitives.schema.json",
+                    include_str!("../schemas/11/geomprimitives.schema.json"),
+                ),
+                (
+                    "appearance.schema.json",
+                    include_str!("../schemas/11/appearance.schema.json"),
+                ),
+                (
+                    "geomtemplates.schema.json",
+                    include_str!("../schemas/11/geomtemplates.schema.json"),
+                ),
+            ],
+        )),
+        20 => Some((
+            "https://www.cityjson.org/schemas/2.0.0",
+            [
+                (
+                    "cityobjects.schema.json",
+                    include_str!("../schemas/20/cityobjects.schema.json"),
+                ),
+                (
+                    "geomprimitives.schema.json",
+                    include_str!("../schemas/20/geomprimitives.schema.json"),
+                ),
+                (
+                    "appearance.schema.json",
+                    include_str!("../schemas/20/appearance.schema.json"),
+                ),
+                (
+                    "geomtemplates.schema.json",
+                    include_str!("../schemas/20/geomtemplates.schema.json"),
+                ),
+            ],
+        )),
+        _ => None,
+    }
+}
+
+/// The base URL and the schemas of CityJSON that an Extension can reference,
+/// parsed only the first time
+pub(crate) fn core_values(
+    version_file: i32,
+) -> Option<(&'static str, &'static [(&'static str, Value)])> {
+    let (base, docs) = core_documents(version_file)?;
+    let i = match version_file {
+        10 => 0,
+        11 => 1,
+        _ => 2,
+    };
+    let values = CORE_VALUES[i].get_or_init(|| {
+        docs.iter()
+            .map(|(name, d)| (*name, serde_json::from_str(d).unwrap()))
+            .collect()
+    });
+    Some((base, values))
+}
+
+/// The "$id" given to the assembled schema of an Extension: v1.0 Extensions
+/// live in "extensions/" and reference the core with "../cityobjects.schema.json"
+pub(crate) fn extension_schema_id(version_file: i32) -> Option<String> {
+    let (base, _) = core_documents(version_file)?;
+    if version_file == 10 {
+        Some(format!("{}/extensions/tmp.json", base))
+    } else {
+        Some(format!("{}/tmp.json", base))
+    }
+}
+
+/// Compiles the (assembled) schema of an Extension with the other Extensions
+/// it references (URL, schema), None if the version has no Extensions
+pub(crate) fn try_compile_extension(
+    version_file: i32,
+    schema: &Value,
+    others: &[(String, Value)],
+) -> Option<Result<JSONSchema, String>> {
+    let (base, docs) = core_values(version_file)?;
+    if let Some(r) = ref_cycle(schema) {
+        return Some(Err(format!("\"$ref\": \"{}\" is an infinite recursion", r)));
+    }
+    let mut options = JSONSchema::options();
+    options.with_draft(Draft::Draft7);
+    for (name, d) in docs.iter() {
+        if version_file == 10 {
+            //-- some v1.0 Extensions omit the "../"
+            options.with_document(format!("{}/extensions/{}", base, name), d.clone());
+        }
+        options.with_document(format!("{}/{}", base, name), d.clone());
+    }
+    for (url, doc) in others {
+        options.with_document(url.to_string(), doc.clone());
+    }
+    Some(options.compile(schema).map_err(|e| e.to_string()))
+}
+
+/// A schema as an object, so that "$id" and the "definitions" can be added
+/// to it: a boolean schema (or an invalid one) is wrapped in "allOf"
+pub(crate) fn object_schema(schema: &Value) -> Value {
+    if schema.is_object() {
+        schema.clone()
+    } else {
+        json!({ "allOf": [schema] })
+    }
+}
+
+/// The files (without the "#..." part) referenced by the "$ref" of a schema
+/// that are not CityJSON schemas, ie other Extensions
+pub(crate) fn external_refs(schema: &Value, version_file: i32) -> Vec<String> {
+    let core: Vec<&str> = match core_documents(version_file) {
+        Some((_, docs)) => docs.iter().map(|(name, _)| *name).collect(),
+        None => Vec::new(),
+    };
+    let mut re: Vec<String> = Vec::new();
+    collect_refs(schema, &mut |r| {
+        let file = r.split('#').next().unwrap_or("");
+        if !file.is_empty()
+            && !core.contains(&file.trim_start_matches("../"))
+            && !re.iter().any(|x| x == file)
+        {
+            re.push(file.to_string());
+        }
+    });
+    re
+}
+
+fn collect_refs(j: &Value, f: &mut impl FnMut(&str)) {
+    match j {
+        Value::Object(o) => {
+            for (k, v) in o {
+                match (k.as_str(), v.as_str()) {
+                    ("$ref", Some(r)) => f(r),
+                    _ => collect_refs(v, f),
+                }
+            }
+        }
+        Value::Array(a) => a.iter().for_each(|v| collect_refs(v, f)),
+        _ => (),
+    }
+}
+
+/// A "$ref" (internal to the schema) that leads back to itself without going
+/// down in the instance (eg through "allOf"), jsonschema would overflow the stack
+fn ref_cycle(schema: &Value) -> Option<String> {
+    let mut refs: Vec<String> = Vec::new();
+    collect_refs(schema, &mut |r| {
+        if r.starts_with('#') && !refs.iter().any(|x| x == r) {
+            refs.push(r.to_string());
+        }
+    });
+    let mut acyclic: Vec<String> = Vec::new();
+    refs.into_iter()
+        .find(|r| in_place_cycle(schema, r, &mut Vec::new(), &mut acyclic))
+}
+
+fn in_place_cycle(
+    root: &Value,
+    r: &str,
+    path: &mut Vec<String>,
+    acyclic: &mut Vec<String>,
+) -> bool {
+    if path.iter().any(|x| x == r) {
+        return true;
+    }
+    if acyclic.iter().any(|x| x == r) {
+        return false;
+    }
+    let Some(target) = root.pointer(&r[1..]) else {
+        return false;
+    };
+    path.push(r.to_string());
+    let mut next: Vec<&str> = Vec::new();
+    in_place_refs(target, &mut next);
+    let re = next
+        .into_iter()
+        .any(|r2| in_place_cycle(root, r2, path, acyclic));
+    path.pop();
+    if !re {
+        acyclic.push(r.to_string());
+    }
+    re
+}
+
+/// The internal "$ref" applied at the same place in the instance
+fn in_place_refs<'a>(s: &'a Value, re: &mut Vec<&'a str>) {
+    if let Some(r) = s["$ref"].as_str() {
+        if r.starts_with('#') {
+            re.push(r);
+        }
+    }
+    for k in ["allOf", "anyOf", "oneOf"] {
+        if let Some(a) = s[k].as_array() {
+            a.iter().for_each(|x| in_place_refs(x, re));
+        }
+    }
+    for k in ["not", "if", "then", "else"] {
+        if s[k].is_object() {
+            in_place_refs(&s[k], re);
+        }
+    }
+}
+
+/// The last segment of a URL or a path, eg "noise.ext.json"
+pub(crate) fn file_name(url: &str) -> &str {
+    let u = url.split(['?', '#']).next().unwrap_or("");
+    u.rsplit('/').next().unwrap_or("")
+}
+
+/// Resolves a (possibly relative) reference against the URL of a schema
+pub(crate) fn join_url(base: &str, r: &str) -> String {
+    if r.contains("://") {
+        return r.to_string();
+    }
+    let mut segments: Vec<&str> = base.split('/').collect();
+    segments.pop();
+    for s in r.split('/') {
+        match s {
+            ".." => {
+                segments.pop();
+            }
+            "." => (),
+            _ => segments.push(s),
+        }
+    }
+    segments.join("/")
+}
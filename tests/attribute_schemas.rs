@@ -0,0 +1,124 @@
+use cjval::CJValidator;
+use serde_json::json;
+use serde_json::Value;
+
+fn get_data() -> Value {
+    json!({
+      "type": "CityJSON",
+      "version": "2.0",
+      "transform": {
+        "scale": [0.001, 0.001, 0.001],
+        "translate": [0.0, 0.0, 0.0]
+      },
+      "CityObjects": {
+        "b1": {
+          "type": "Building",
+          "attributes": {"status": "Pand in gebruik", "yearOfConstruction": 1985}
+        },
+        "b2": {
+          "type": "Building",
+          "attributes": {"status": "Bouw gestart"}
+        },
+        "t1": {
+          "type": "SolitaryVegetationObject"
+        }
+      },
+      "vertices": []
+    })
+}
+
+fn get_schemas() -> Value {
+    json!({
+      "Building": {
+        "type": "object",
+        "properties": {
+          "status": {"enum": ["Pand in gebruik", "Bouw gestart"]},
+          "yearOfConstruction": {"type": "integer", "minimum": 1000}
+        },
+        "required": ["status"]
+      }
+    })
+}
+
+fn validate(j: &Value, schemas: &Value) -> Result<(), Vec<String>> {
+    let mut v = CJValidator::from_str(&j.to_string());
+    v.add_attribute_schemas_from_str(&schemas.to_string())
+        .unwrap();
+    let re = v.validate();
+    assert!(re["schema"].is_valid());
+    match re["attribute_schemas"].is_valid() {
+        true => Ok(()),
+        false => Err(re["attribute_schemas"].get_errors().clone()),
+    }
+}
+
+#[test]
+fn valid() {
+    assert!(validate(&get_data(), &get_schemas()).is_ok());
+    //-- no schema: the check is not performed
+    let v = CJValidator::from_str(&get_data().to_string());
+    let re = v.validate();
+    assert!(!re["attribute_schemas"].is_valid());
+    assert!(!re["attribute_schemas"].has_errors());
+}
+
+#[test]
+fn wrong_value() {
+    let mut j = get_data();
+    j["CityObjects"]["b2"]["attributes"]["yearOfConstruction"] = json!("1985");
+    let errors = validate(&j, &get_schemas()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("/CityObjects/b2/attributes/yearOfConstruction -- "));
+}
+
+#[test]
+fn missing_attribute() {
+    let mut j = get_data();
+    j["CityObjects"]["b1"]["attributes"]
+        .as_object_mut()
+        .unwrap()
+        .remove("status");
+    j["CityObjects"]["b2"]
+        .as_object_mut()
+        .unwrap()
+        .remove("attributes");
+    let errors = validate(&j, &get_schemas()).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].starts_with("/CityObjects/b1/attributes -- "));
+    assert!(errors[0].contains("status"));
+    assert!(errors[1].starts_with("/CityObjects/b2/attributes -- "));
+}
+
+#[test]
+fn ref_to_cityjson_schemas() {
+    let mut j = get_data();
+    j["CityObjects"]["b1"]["attributes"]["colour"] =
+        json!({"name": "red", "diffuseColor": [1.0, 0.0]});
+    let schemas = json!({
+      "Building": {
+        "properties": {"colour": {"$ref": "appearance.schema.json#/Material"}}
+      }
+    });
+    let errors = validate(&j, &schemas).unwrap_err();
+    assert!(errors[0].starts_with("/CityObjects/b1/attributes/colour -- "));
+    assert!(errors[0].ends_with("[path:/colour/diffuseColor]"));
+}
+
+#[test]
+fn schema_cannot_be_compiled() {
+    let schemas = json!({"Building": {"type": "text"}});
+    let mut v = CJValidator::from_str(&get_data().to_string());
+    let e = v
+        .add_attribute_schemas_from_str(&schemas.to_string())
+        .unwrap_err();
+    assert!(e.to_string().contains("'Building'"));
+    //-- the data is not invalid because of it
+    assert!(v.is_valid());
+}
+
+#[test]
+fn not_an_object() {
+    let mut v = CJValidator::from_str(&get_data().to_string());
+    assert!(v.add_attribute_schemas_from_str("[1, 2]").is_err());
+    assert!(v.add_attribute_schemas_from_str("{").is_err());
+}
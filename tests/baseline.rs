@@ -0,0 +1,122 @@
+use cjval::{Baseline, CJValidator};
+use serde_json::json;
+use serde_json::Value;
+
+fn get_data() -> Value {
+    let s = std::fs::read_to_string("data/twobuildings.city.json").unwrap();
+    let mut j: Value = serde_json::from_str(&s).unwrap();
+    //-- 2 wrong vertex indices
+    *j.pointer_mut("/CityObjects/b1/geometry/0/boundaries/0/0/0")
+        .unwrap() = json!(999);
+    *j.pointer_mut("/CityObjects/b1-p1/geometry/0/boundaries/0/0/0")
+        .unwrap() = json!(998);
+    j
+}
+
+fn validate(j: &Value, baseline: &str) -> CJValidator {
+    let mut v = CJValidator::from_str(&j.to_string());
+    v.set_baseline(Baseline::from_json_str(baseline).unwrap());
+    v
+}
+
+#[test]
+fn diagnostics_of_builtin_checks() {
+    let v = CJValidator::from_str(&get_data().to_string());
+    let re = v.validate();
+    let ds = re["wrong_vertex_index"].get_diagnostics();
+    assert_eq!(ds.len(), 2);
+    assert_eq!(ds[0].cityobject.as_deref(), Some("b1"));
+    assert_eq!(ds[1].cityobject.as_deref(), Some("b1-p1"));
+}
+
+#[test]
+fn by_check() {
+    let v = validate(
+        &get_data(),
+        r#"{"ignore": [{"check": "wrong_vertex_index"}]}"#,
+    );
+    let re = v.validate();
+    assert!(re["wrong_vertex_index"].is_valid());
+    assert_eq!(re["wrong_vertex_index"].get_ignored().len(), 2);
+    assert!(v.is_valid());
+}
+
+#[test]
+fn by_cityobject() {
+    let b = r#"{"ignore": [
+      {"check": "wrong_vertex_index", "cityobject": "b1"},
+      {"check": "wrong_vertex_index", "cityobject": "b1-p1"}
+    ]}"#;
+    assert!(validate(&get_data(), b).is_valid());
+    let b = r#"{"ignore": [{"check": "wrong_vertex_index", "cityobject": "b1"}]}"#;
+    let re = validate(&get_data(), b).validate();
+    assert_eq!(re["wrong_vertex_index"].get_errors().len(), 1);
+    assert!(re["wrong_vertex_index"].get_errors()[0].contains("#b1-p1"));
+    let b = r#"{"ignore": [{"check": "textures", "cityobject": "b1"}]}"#;
+    assert!(!validate(&get_data(), b).is_valid());
+}
+
+#[test]
+fn by_pointer() {
+    let mut j = get_data();
+    j["CityObjects"]["b1"]["geometry"][0]["lod"] = json!(12);
+    let b = r#"{"ignore": [
+      {"check": "schema", "pointer": "/CityObjects"},
+      {"check": "wrong_vertex_index"}
+    ]}"#;
+    let re = validate(&j, b).validate();
+    assert!(re["schema"].is_valid());
+    assert!(!re["schema"].get_ignored().is_empty());
+    //-- the pointers are compared by segments
+    let b = r#"{"ignore": [
+      {"check": "schema", "pointer": "/CityObjects/b"},
+      {"check": "wrong_vertex_index"}
+    ]}"#;
+    assert!(validate(&j, b).validate()["schema"].has_errors());
+}
+
+#[test]
+fn new_problems_are_reported() {
+    //-- the baseline written for the file ignores all its errors
+    let v = CJValidator::from_str(&get_data().to_string());
+    let mut baseline = Baseline::new();
+    baseline.add_results(&v.validate());
+    assert_eq!(baseline.len(), 2);
+    let b = baseline.to_json_string();
+    assert!(validate(&get_data(), &b).is_valid());
+    //-- but not a new one
+    let mut j = get_data();
+    *j.pointer_mut("/CityObjects/b2/geometry/0/boundaries/0/0/0")
+        .unwrap() = json!(997);
+    let re = validate(&j, &b).validate();
+    assert_eq!(re["wrong_vertex_index"].get_errors().len(), 1);
+    assert_eq!(re["wrong_vertex_index"].get_ignored().len(), 2);
+}
+
+#[test]
+fn invalid_baseline() {
+    assert!(Baseline::from_json_str(r#"{"ignore": [{"cityobject": "b1"}]}"#).is_err());
+    assert!(Baseline::from_json_str(r#"{"ignores": []}"#).is_err());
+}
+
+#[test]
+fn duplicate_vertices_by_cityobject() {
+    //-- b2 uses a copy of the 1st vertex
+    let mut j: Value =
+        serde_json::from_str(&std::fs::read_to_string("data/twobuildings.city.json").unwrap())
+            .unwrap();
+    let v0 = j["vertices"][0].clone();
+    j["vertices"].as_array_mut().unwrap().push(v0);
+    *j.pointer_mut("/CityObjects/b2/geometry/0/boundaries/0/0/0")
+        .unwrap() = json!(12);
+    let v = CJValidator::from_str(&j.to_string());
+    let ds = v.validate()["duplicate_vertices"]
+        .get_diagnostics()
+        .to_vec();
+    assert_eq!(ds.len(), 1);
+    assert_eq!(ds[0].cityobject.as_deref(), Some("b2"));
+    let b = r#"{"ignore": [{"check": "duplicate_vertices", "cityobject": "b2"}]}"#;
+    let re = validate(&j, b).validate();
+    assert!(!re["duplicate_vertices"].has_errors());
+    assert_eq!(re["duplicate_vertices"].get_ignored().len(), 1);
+}
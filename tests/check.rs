@@ -0,0 +1,139 @@
+use cjval::{CJValidator, Check, Diagnostic, Severity, ValidationContext, ValidationOptions};
+use serde_json::json;
+use serde_json::Value;
+
+fn get_data() -> Value {
+    json!({
+      "type": "CityJSON",
+      "version": "2.0",
+      "transform": {
+        "scale": [0.001, 0.001, 0.001],
+        "translate": [0.0, 0.0, 0.0]
+      },
+      "CityObjects": {
+        "b1": {"type": "Building", "attributes": {"height": 12.1}},
+        "b2": {"type": "Building"}
+      },
+      "vertices": []
+    })
+}
+
+struct BuildingHeight {
+    severity: Severity,
+}
+
+impl Check for BuildingHeight {
+    fn name(&self) -> &str {
+        "building_height"
+    }
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+    fn run(&self, ctx: &ValidationContext) -> Vec<Diagnostic> {
+        ctx.cityobjects()
+            .filter(|(_, co)| co["type"] == "Building" && co["attributes"].get("height").is_none())
+            .map(|(id, _)| {
+                Diagnostic::new("no height")
+                    .with_cityobject(id)
+                    .with_pointer(&format!("/CityObjects/{}/attributes", id))
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn added_check_error() {
+    let mut v = CJValidator::from_str(&get_data().to_string());
+    v.add_check(BuildingHeight {
+        severity: Severity::Error,
+    })
+    .unwrap();
+    let re = v.validate();
+    assert!(re["schema"].is_valid());
+    let summ = &re["building_height"];
+    assert!(summ.has_errors());
+    assert!(!summ.is_warning());
+    assert_eq!(
+        summ.get_errors(),
+        &vec!["CityObject #b2: no height".to_string()]
+    );
+    let d = &summ.get_diagnostics()[0];
+    assert_eq!(d.cityobject.as_deref(), Some("b2"));
+    assert_eq!(d.pointer.as_deref(), Some("/CityObjects/b2/attributes"));
+    assert!(!v.is_valid());
+    //-- the errors are performed before the warnings
+    let keys: Vec<&String> = re.keys().collect();
+    let i = keys.iter().position(|k| *k == "building_height").unwrap();
+    assert_eq!(keys[i + 1], "extra_root_properties");
+}
+
+#[test]
+fn added_check_warning() {
+    let mut v = CJValidator::from_str(&get_data().to_string());
+    v.add_check(BuildingHeight {
+        severity: Severity::Warning,
+    })
+    .unwrap();
+    let re = v.validate();
+    assert!(re["building_height"].has_errors());
+    assert!(re["building_height"].is_warning());
+    assert_eq!(re.keys().last().unwrap(), "building_height");
+    assert!(v.is_valid());
+}
+
+#[test]
+fn not_performed_after_schema_errors() {
+    let mut j = get_data();
+    j["CityObjects"]["b2"]["type"] = json!("House");
+    let mut v = CJValidator::from_str(&j.to_string());
+    v.add_check(BuildingHeight {
+        severity: Severity::Error,
+    })
+    .unwrap();
+    let re = v.validate();
+    assert!(re["schema"].has_errors());
+    assert!(!re["building_height"].is_valid());
+    assert!(!re["building_height"].has_errors());
+}
+
+#[test]
+fn name_already_used() {
+    struct Schema;
+    impl Check for Schema {
+        fn name(&self) -> &str {
+            "schema"
+        }
+        fn run(&self, _ctx: &ValidationContext) -> Vec<Diagnostic> {
+            Vec::new()
+        }
+    }
+    let mut v = CJValidator::from_str(&get_data().to_string());
+    assert!(v.add_check(Schema).is_err());
+    let h = || BuildingHeight {
+        severity: Severity::Error,
+    };
+    assert!(v.add_check(h()).is_ok());
+    assert!(v.add_check(h()).is_err());
+}
+
+#[test]
+fn added_check_options() {
+    let mut v = CJValidator::from_str(&get_data().to_string());
+    v.add_check(BuildingHeight {
+        severity: Severity::Error,
+    })
+    .unwrap();
+    assert!(v.get_check_names().contains(&"building_height".to_string()));
+    let mut opts = ValidationOptions::new();
+    opts.set("building_height", Severity::Warning).unwrap();
+    v.set_options(opts.clone());
+    assert!(v.validate()["building_height"].is_warning());
+    assert!(v.is_valid());
+    assert_eq!(
+        v.get_options().to_string(),
+        "default (building_height=warning)"
+    );
+    opts.set("building_height", Severity::Off).unwrap();
+    v.set_options(opts);
+    assert!(v.validate()["building_height"].is_disabled());
+}
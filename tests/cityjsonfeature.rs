@@ -192,3 +192,165 @@ fn cjfeature_extension() {
     assert!(re["schema"].is_valid());
     assert!(re["extensions"].is_valid());
 }
+
+#[test]
+fn cjfeature_extension_root_properties() {
+    let j = get_first_line();
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let s = std::fs::read_to_string("schemas/extensions/20/generic.ext.json").unwrap();
+    let mut jext: Value = serde_json::from_str(&s).unwrap();
+    jext["extraRootProperties"] = json!({"+census": {"type": "integer"}});
+    let _ = v.add_one_extension_from_str(&jext.to_string());
+
+    let mut jf = get_third_line();
+    jf["+census"] = json!(12);
+    let _ = v.from_str_cjfeature_at(&jf.to_string(), 3);
+    let mut re = v.validate();
+    assert!(re["extensions"].is_valid());
+    assert!(re["extra_root_properties"].is_valid());
+
+    jf["+census"] = json!("twelve");
+    let _ = v.from_str_cjfeature_at(&jf.to_string(), 4);
+    re = v.validate();
+    assert!(!re["extensions"].is_valid());
+    assert!(re["extensions"]
+        .get_errors()
+        .iter()
+        .all(|e| e.ends_with("[feature:id-1, line:4]")));
+
+    jf["+census"] = json!(12);
+    jf["+other"] = json!(12);
+    let _ = v.from_str_cjfeature_at(&jf.to_string(), 5);
+    re = v.validate();
+    assert!(!re["extensions"].is_valid());
+
+    jf.as_object_mut().unwrap().remove("+other");
+    jf["foo"] = json!(12);
+    let _ = v.from_str_cjfeature_at(&jf.to_string(), 6);
+    re = v.validate();
+    assert!(re["extensions"].is_valid());
+    assert!(!re["extra_root_properties"].is_valid());
+}
+
+fn get_first_line_templates() -> Value {
+    let j_1 = r#"
+        {
+          "type": "CityJSON",
+          "version": "2.0",
+          "CityObjects": {},
+          "vertices": [],
+          "transform": {
+            "scale": [0.001, 0.001, 0.001],
+            "translate": [-1.0, -1.0, 0.0]
+          },
+          "appearance": {
+            "materials": [ { "name": "roofandground" } ]
+          },
+          "geometry-templates": {
+            "templates": [
+              {
+                "type": "MultiSurface",
+                "lod": "2",
+                "boundaries": [ [[0, 1, 2]] ]
+              }
+            ],
+            "vertices-templates": [ [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0] ]
+          }
+        }
+        "#;
+    let v: Value = serde_json::from_str(j_1).unwrap();
+    v
+}
+
+fn get_feature_templates() -> Value {
+    let j_1 = r#"
+        {
+          "type": "CityJSONFeature",
+          "id": "id-1",
+          "CityObjects": {
+            "id-1": {
+              "type": "SolitaryVegetationObject",
+              "geometry": [
+                {
+                  "type": "GeometryInstance",
+                  "template": 0,
+                  "boundaries": [0],
+                  "transformationMatrix": [
+                    1.0, 0.0, 0.0, 0.0,
+                    0.0, 1.0, 0.0, 0.0,
+                    0.0, 0.0, 1.0, 0.0,
+                    0.0, 0.0, 0.0, 1.0
+                  ]
+                },
+                {
+                  "type": "MultiSurface",
+                  "lod": "1",
+                  "boundaries": [ [[1, 2, 3]] ],
+                  "material": {
+                    "irradiation": { "values": [0] }
+                  }
+                }
+              ]
+            }
+          },
+          "vertices": [ [12, 3, 4], [1, 23, 4], [0, 0, 0], [1000, 0, 0] ]
+        }
+        "#;
+    let v: Value = serde_json::from_str(j_1).unwrap();
+    v
+}
+
+#[test]
+fn cjfeature_uses_header() {
+    let j = get_first_line_templates();
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let mut re = v.validate();
+    assert!(re["schema"].is_valid());
+
+    let mut jf = get_feature_templates();
+    let _ = v.from_str_cjfeature(&jf.to_string());
+    re = v.validate();
+    assert!(re["schema"].is_valid());
+    assert!(re["wrong_vertex_index"].is_valid());
+    assert!(re["materials"].is_valid());
+    assert!(re["duplicate_vertices"].is_valid());
+
+    //-- template doesn't exist in the header
+    *jf.pointer_mut("/CityObjects/id-1/geometry/0/template")
+        .unwrap() = json!(1);
+    let _ = v.from_str_cjfeature(&jf.to_string());
+    re = v.validate();
+    assert!(!re["wrong_vertex_index"].is_valid());
+
+    //-- material doesn't exist in the header
+    jf = get_feature_templates();
+    *jf.pointer_mut("/CityObjects/id-1/geometry/1/material/irradiation/values/0")
+        .unwrap() = json!(1);
+    let _ = v.from_str_cjfeature(&jf.to_string());
+    re = v.validate();
+    assert!(!re["materials"].is_valid());
+
+    //-- duplicate vertices
+    jf = get_feature_templates();
+    *jf.pointer_mut("/vertices/1").unwrap() = json!([12, 3, 4]);
+    let _ = v.from_str_cjfeature(&jf.to_string());
+    re = v.validate();
+    assert!(!re["duplicate_vertices"].is_valid());
+}
+
+#[test]
+fn cjfeature_extra_root_properties_v11() {
+    let mut j = get_first_line();
+    j["version"] = json!("1.1");
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let mut opts = cjval::ValidationOptions::new();
+    opts.set_exhaustive(true);
+    v.set_options(opts);
+    let mut jf = get_third_line();
+    jf["foo"] = json!(12);
+    let _ = v.from_str_cjfeature(&jf.to_string());
+    let re = v.validate();
+    //-- not allowed by the v1.1 schema, so not reported twice
+    assert!(!re["schema"].is_valid());
+    assert!(re["extra_root_properties"].is_valid());
+}
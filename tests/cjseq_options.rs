@@ -0,0 +1,85 @@
+//-- the options of the binary to validate only parts of a CityJSONSeq stream
+#![cfg(feature = "build-binary")]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+//-- the 1st line then 20 features, the 2nd one is invalid
+fn get_stream(name: &str) -> PathBuf {
+    let s = std::fs::read_to_string("data/twobuildings.city.jsonl").unwrap();
+    let lines: Vec<&str> = s.lines().collect();
+    let mut out = vec![lines[0].to_string()];
+    for i in 0..20 {
+        if i == 1 {
+            out.push(lines[2].replace("\"Building\"", "\"Buildingg\""));
+        } else {
+            out.push(lines[2].to_string());
+        }
+    }
+    let p = std::env::temp_dir().join(format!("cjval-{}-{}.jsonl", name, std::process::id()));
+    std::fs::write(&p, out.join("\n")).unwrap();
+    p
+}
+
+//-- the lines of the features validated, and the summary
+fn run(name: &str, args: &[&str]) -> (Vec<usize>, String) {
+    let p = get_stream(name);
+    let o = Command::new(env!("CARGO_BIN_EXE_cjval"))
+        .arg(&p)
+        .args(args)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&p).unwrap();
+    let stdout = String::from_utf8(o.stdout).unwrap();
+    let lines = stdout
+        .lines()
+        .filter_map(|l| l.split('\t').next()?.parse::<usize>().ok())
+        .filter(|i| *i > 1)
+        .collect();
+    let summary = stdout.split("SUMMARY").nth(1).unwrap().to_string();
+    (lines, summary)
+}
+
+#[test]
+fn all_features() {
+    let (lines, summary) = run("all", &[]);
+    assert_eq!(lines, (2..=21).collect::<Vec<usize>>());
+    assert!(summary.contains("CityJSONSeq has invalid objects"));
+    assert!(!summary.contains("Features validated"));
+}
+
+#[test]
+fn start_line() {
+    let (lines, summary) = run("start", &["--start-line", "4"]);
+    assert_eq!(lines, (4..=21).collect::<Vec<usize>>());
+    assert!(summary.contains("Features validated: 18"));
+    assert!(summary.contains("✅ The sampled features of the CityJSONSeq are valid"));
+}
+
+#[test]
+fn max_features() {
+    let (lines, summary) = run("max", &["--max-features", "5"]);
+    assert_eq!(lines, vec![2, 3, 4, 5, 6]);
+    assert!(summary.contains("Stopped after 5 validated feature(s)"));
+    assert!(summary.contains("CityJSONSeq has invalid objects"));
+}
+
+#[test]
+fn every() {
+    let (lines, summary) = run("every", &["--every", "5"]);
+    assert_eq!(lines, vec![2, 7, 12, 17]);
+    assert!(summary.contains("Features validated: 4"));
+    assert!(summary.contains("The sampled features of the CityJSONSeq are valid"));
+}
+
+#[test]
+fn sample() {
+    let (lines1, summary) = run("sample1", &["--sample", "0.5", "--seed", "7"]);
+    assert!(lines1.len() < 20);
+    assert!(summary.contains(&format!("Features validated: {}", lines1.len())));
+    //-- the same seed gives the same sample
+    let (lines2, _) = run("sample2", &["--sample", "0.5", "--seed", "7"]);
+    assert_eq!(lines1, lines2);
+    let (lines3, _) = run("sample3", &["--sample", "0.0"]);
+    assert!(lines3.is_empty());
+}
@@ -0,0 +1,69 @@
+//-- the exit codes of cjval
+#![cfg(feature = "build-binary")]
+
+use serde_json::json;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn run(args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_cjval"))
+        .args(args)
+        .arg("--quiet")
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+fn write_tmp(name: &str, s: &str) -> PathBuf {
+    let p = std::env::temp_dir().join(format!("cjval-exit-{}-{}", std::process::id(), name));
+    std::fs::write(&p, s).unwrap();
+    p
+}
+
+//-- a wrong vertex index in b1
+fn get_invalid() -> Value {
+    let s = std::fs::read_to_string("data/twobuildings.city.json").unwrap();
+    let mut j: Value = serde_json::from_str(&s).unwrap();
+    *j.pointer_mut("/CityObjects/b1/geometry/0/boundaries/0/0/0")
+        .unwrap() = json!(999);
+    j
+}
+
+#[test]
+fn valid() {
+    assert_eq!(run(&["data/twobuildings.city.json"]), 0);
+}
+
+#[test]
+fn invalid() {
+    let p = write_tmp("invalid.city.json", &get_invalid().to_string());
+    assert_eq!(run(&[p.to_str().unwrap()]), 1);
+    std::fs::remove_file(&p).unwrap();
+}
+
+#[test]
+fn invalid_with_baseline() {
+    let p = write_tmp("baseline.city.json", &get_invalid().to_string());
+    let b = write_tmp(
+        "baseline.json",
+        r#"{"ignore": [{"check": "wrong_vertex_index", "cityobject": "b1"}]}"#,
+    );
+    assert_eq!(
+        run(&[p.to_str().unwrap(), "--baseline", b.to_str().unwrap()]),
+        0
+    );
+    std::fs::remove_file(&p).unwrap();
+    std::fs::remove_file(&b).unwrap();
+}
+
+#[test]
+fn cjseq() {
+    assert_eq!(run(&["data/twobuildings.city.jsonl"]), 0);
+    let s = std::fs::read_to_string("data/twobuildings.city.jsonl").unwrap();
+    let p = write_tmp("invalid.jsonl", &s.replace("\"Building\"", "\"Buildingg\""));
+    assert_eq!(run(&[p.to_str().unwrap()]), 1);
+    std::fs::remove_file(&p).unwrap();
+}
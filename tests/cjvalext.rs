@@ -0,0 +1,68 @@
+//-- the exit codes of cjvalext
+#![cfg(feature = "build-binary")]
+
+use std::process::Command;
+
+fn run(args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_cjvalext"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn valid() {
+    assert_eq!(run(&["schemas/extensions/11/noise.ext.json"]), 0);
+    assert_eq!(
+        run(&[
+            "schemas/extensions/11/noise.ext.json",
+            "--sample",
+            "data/noise1.city.json"
+        ]),
+        0
+    );
+}
+
+#[test]
+fn missing_input() {
+    assert_eq!(run(&["data/nothere.ext.json"]), 1);
+}
+
+#[test]
+fn invalid_extension() {
+    let p = std::env::temp_dir().join(format!("cjvalext-{}.ext.json", std::process::id()));
+    std::fs::write(
+        &p,
+        r#"{"type": "CityJSONExtension", "name": "Noise", "versionCityJSON": "1.1"}"#,
+    )
+    .unwrap();
+    let code = run(&[p.to_str().unwrap()]);
+    std::fs::remove_file(&p).unwrap();
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn invalid_sample() {
+    assert_eq!(
+        run(&[
+            "schemas/extensions/11/noise.ext.json",
+            "--sample",
+            "data/nothere.city.json"
+        ]),
+        1
+    );
+    //-- a v2.0 file with a v1.1 Extension, after a valid sample
+    assert_eq!(
+        run(&[
+            "schemas/extensions/11/noise.ext.json",
+            "--sample",
+            "data/noise1.city.json",
+            "--sample",
+            "data/cube.city.json"
+        ]),
+        1
+    );
+}
@@ -33,3 +33,24 @@ fn contains_same_key() {
     let re = v.validate();
     assert!(re["schema"].is_valid());
 }
+
+#[test]
+fn duplicate_keys_cjfeature() {
+    let j_header = r#"{"type":"CityJSON","version":"2.0","CityObjects":{},"vertices":[],"transform":{"scale":[0.001,0.001,0.001],"translate":[0.0,0.0,0.0]}}"#;
+    let mut v: CJValidator = CJValidator::from_str(j_header);
+    let j_feature = r#"{"type":"CityJSONFeature","id":"a","CityObjects":{"a":{"type":"Building"},"a":{"type":"Building"}},"vertices":[]}"#;
+    let _ = v.from_str_cjfeature(j_feature);
+    let re = v.validate();
+    assert!(!re["schema"].is_valid());
+}
+
+#[test]
+fn duplicate_keys_in_str() {
+    let j = r#"{"type":"CityJSON","version":"1.1","CityObjects":{"LondonTower":{"type":"Building"},"LondonTower":{"type":"WaterBody"}},"vertices":[],"transform":{"scale":[0.001,0.001,0.001],"translate":[0.0,0.0,0.0]}}"#;
+    let v: CJValidator = CJValidator::from_str(j);
+    let re = v.validate();
+    assert!(!re["schema"].is_valid());
+    let v: CJValidator = CJValidator::from_reader(j.as_bytes());
+    let re = v.validate();
+    assert!(!re["schema"].is_valid());
+}
@@ -0,0 +1,127 @@
+use cjval::CJValidator;
+use cjval::Equivalence;
+use cjval::EquivalenceReport;
+use serde_json::json;
+use serde_json::Value;
+
+fn get_seq() -> Vec<Value> {
+    let s = std::fs::read_to_string("data/twobuildings.city.jsonl").unwrap();
+    s.lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+fn compare(lines: &[Value]) -> EquivalenceReport {
+    let s = std::fs::read_to_string("data/twobuildings.city.json").unwrap();
+    let cj = CJValidator::from_str(&s);
+    let mut eq = Equivalence::new(&cj).unwrap();
+    let mut v = CJValidator::from_str(&lines[0].to_string());
+    for l in &lines[1..] {
+        v.from_str_cjfeature(&l.to_string()).unwrap();
+        eq.add_feature(&v);
+    }
+    eq.finish()
+}
+
+#[test]
+fn equivalent() {
+    let lines = get_seq();
+    let re = compare(&lines);
+    assert!(re.is_equivalent());
+}
+
+#[test]
+fn missing_and_extra() {
+    let mut lines = get_seq();
+    lines.pop();
+    let re = compare(&lines);
+    assert!(!re.is_equivalent());
+    assert_eq!(re.missing, vec!["b2".to_string()]);
+
+    let mut lines = get_seq();
+    let l = lines[2].clone();
+    lines.push(l);
+    let re = compare(&lines);
+    assert_eq!(re.extra, vec!["b2".to_string()]);
+}
+
+#[test]
+fn different() {
+    let mut lines = get_seq();
+    *lines[1]
+        .pointer_mut("/CityObjects/b1/attributes/yearOfConstruction")
+        .unwrap() = json!(2000);
+    let re = compare(&lines);
+    assert_eq!(re.different.len(), 1);
+    assert_eq!(re.different[0].0, "b1");
+
+    //-- the material of the roof is not the same
+    let mut lines = get_seq();
+    *lines[2]
+        .pointer_mut("/CityObjects/b2/geometry/0/material/default/value")
+        .unwrap() = json!(1);
+    lines[2]["appearance"]["materials"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({"name": "wall"}));
+    let re = compare(&lines);
+    assert_eq!(re.different.len(), 1);
+}
+
+#[test]
+fn transform_precision() {
+    //-- other translate, the coordinates are the same within the precision
+    let mut lines = get_seq();
+    lines[0]["transform"]["translate"] = json!([85000.0005, 446000.0, 0.0]);
+    let re = compare(&lines);
+    assert!(re.is_equivalent());
+
+    lines[0]["transform"]["translate"] = json!([85000.01, 446000.0, 0.0]);
+    let re = compare(&lines);
+    assert_eq!(re.different.len(), 3);
+}
+
+#[test]
+fn address_location() {
+    let cj = json!({
+      "type": "CityJSON",
+      "version": "2.0",
+      "transform": {"scale": [0.001, 0.001, 0.001], "translate": [0.0, 0.0, 0.0]},
+      "CityObjects": {
+        "a": {
+          "type": "Building",
+          "address": [{
+            "country": "NL",
+            "location": {"type": "MultiPoint", "lod": "1", "boundaries": [4]}
+          }],
+          "geometry": [{"type": "MultiSurface", "lod": "1", "boundaries": [[[0, 1, 2, 3]]]}]
+        }
+      },
+      "vertices": [[0, 0, 0], [1000, 0, 0], [1000, 1000, 0], [0, 1000, 0], [500, 500, 0]]
+    });
+    let mut eq = Equivalence::new(&CJValidator::from_str(&cj.to_string())).unwrap();
+    //-- the vertices are in another order in the CityJSONFeature
+    let f = json!({
+      "type": "CityJSONFeature",
+      "id": "a",
+      "CityObjects": {
+        "a": {
+          "type": "Building",
+          "address": [{
+            "country": "NL",
+            "location": {"type": "MultiPoint", "lod": "1", "boundaries": [1]}
+          }],
+          "geometry": [{"type": "MultiSurface", "lod": "1", "boundaries": [[[0, 2, 3, 4]]]}]
+        }
+      },
+      "vertices": [[0, 0, 0], [500, 500, 0], [1000, 0, 0], [1000, 1000, 0], [0, 1000, 0]]
+    });
+    let mut header = cj.clone();
+    header["CityObjects"] = json!({});
+    header["vertices"] = json!([]);
+    let mut v = CJValidator::from_str(&header.to_string());
+    v.from_str_cjfeature(&f.to_string()).unwrap();
+    eq.add_feature(&v);
+    let re = eq.finish();
+    assert!(re.is_equivalent(), "{}", re);
+}
@@ -0,0 +1,114 @@
+use cjval::{CJValidator, ValidationOptions};
+use serde_json::json;
+use serde_json::Value;
+
+//-- 250 different duplicated vertices, each one 3 times
+fn get_data() -> Value {
+    let mut vs: Vec<Value> = Vec::new();
+    for i in 0..250 {
+        for _ in 0..3 {
+            vs.push(json!([i, 0, 0]));
+        }
+    }
+    json!({
+      "type": "CityJSON",
+      "version": "2.0",
+      "transform": {
+        "scale": [1.0, 1.0, 1.0],
+        "translate": [0.0, 0.0, 0.0]
+      },
+      "CityObjects": {
+        "b1": {"type": "Building"}
+      },
+      "vertices": vs
+    })
+}
+
+fn validate(j: &Value, limit: Option<usize>) -> CJValidator {
+    let mut v = CJValidator::from_str(&j.to_string());
+    let mut opts = ValidationOptions::new();
+    opts.set("duplicate_vertices", cjval::Severity::Error)
+        .unwrap();
+    opts.set("unused_vertices", cjval::Severity::Off).unwrap();
+    opts.set_error_limit(limit);
+    opts.set_exhaustive(true);
+    v.set_options(opts);
+    v
+}
+
+#[test]
+fn identical_errors_are_grouped() {
+    let re = validate(&get_data(), None).validate();
+    let summ = &re["duplicate_vertices"];
+    assert_eq!(summ.get_errors().len(), 250);
+    assert_eq!(
+        summ.get_errors()[0],
+        "Vertex (0, 0, 0) duplicated (2 times)"
+    );
+    assert_eq!(summ.get_diagnostics()[0].count, 2);
+    assert_eq!(summ.get_error_count(), 500);
+}
+
+#[test]
+fn no_limit_by_default() {
+    let re = CJValidator::from_str(&get_data().to_string()).validate();
+    let summ = &re["duplicate_vertices"];
+    assert_eq!(summ.get_diagnostics().len(), 250);
+    assert_eq!(summ.get_errors().len(), 250);
+    assert_eq!(summ.get_error_count(), 500);
+}
+
+#[test]
+fn other_limit() {
+    let re = validate(&get_data(), Some(10)).validate();
+    let summ = &re["duplicate_vertices"];
+    assert_eq!(summ.get_errors().len(), 11);
+    assert_eq!(summ.get_errors()[10], "... and 480 more");
+    assert_eq!(summ.get_error_count(), 500);
+    //-- the limit is for each check
+    let mut j = get_data();
+    j["CityObjects"]["b1"]["geometry"] = json!([{
+      "type": "MultiSurface",
+      "lod": "1",
+      "boundaries": (1000..1020).map(|i| [[i, i + 1, i + 2]]).collect::<Vec<_>>()
+    }]);
+    let re = validate(&j, Some(10)).validate();
+    assert_eq!(re["wrong_vertex_index"].get_errors().len(), 11);
+    assert_eq!(
+        re["wrong_vertex_index"].get_errors()[0],
+        "Vertices 1000 don't exist (in #b1)"
+    );
+    assert_eq!(re["wrong_vertex_index"].get_error_count(), 60);
+    assert_eq!(re["duplicate_vertices"].get_errors().len(), 11);
+}
+
+#[test]
+fn thousands_separator() {
+    let mut vs: Vec<Value> = Vec::new();
+    for i in 0..1002 {
+        vs.push(json!([i, 0, 0]));
+        vs.push(json!([i, 0, 0]));
+    }
+    let mut j = get_data();
+    j["vertices"] = json!(vs);
+    let re = validate(&j, Some(1)).validate();
+    assert_eq!(
+        re["duplicate_vertices"].get_errors()[1],
+        "... and 1,001 more"
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn from_toml() {
+    let opts = ValidationOptions::from_toml_str("error_limit = 5").unwrap();
+    assert_eq!(opts.error_limit(), Some(5));
+    let opts = ValidationOptions::from_toml_str("error_limit = 0").unwrap();
+    assert_eq!(opts.error_limit(), None);
+    assert_eq!(ValidationOptions::new().error_limit(), None);
+    //-- a limit set before is kept if the file doesn't give one
+    let mut opts = ValidationOptions::new();
+    opts.set_error_limit(Some(100));
+    opts.update_from_toml_str("exhaustive = true").unwrap();
+    assert_eq!(opts.error_limit(), Some(100));
+}
@@ -0,0 +1,106 @@
+use serde_json::json;
+use serde_json::Value;
+
+fn get_noise_20() -> Value {
+    let s = std::fs::read_to_string("schemas/extensions/20/noise.ext.json").unwrap();
+    serde_json::from_str(&s).unwrap()
+}
+
+fn diff(old: &Value, new: &Value) -> cjval::ExtensionDiff {
+    cjval::diff_extensions_str(&old.to_string(), &new.to_string()).unwrap()
+}
+
+#[test]
+fn same_extension() {
+    let old = get_noise_20();
+    let mut new = get_noise_20();
+    new["description"] = json!("a better description");
+    new["definitions"]["measure"]["description"] = json!("a measure");
+    let re = diff(&old, &new);
+    assert!(re.changes.is_empty());
+    assert!(!re.is_breaking());
+}
+
+#[test]
+fn added_removed() {
+    let old = get_noise_20();
+    let mut new = get_noise_20();
+    new["extraRootProperties"]["+census"] = json!({"type": "integer"});
+    new["extraAttributes"]["Road"] = json!({"+noise": {"type": "integer"}});
+    let re = diff(&old, &new);
+    assert_eq!(re.changes.len(), 2);
+    assert!(!re.is_breaking());
+
+    //-- the other way around
+    let re = diff(&new, &old);
+    assert_eq!(re.changes.len(), 2);
+    assert!(re.changes.iter().all(|c| c.breaking));
+}
+
+#[test]
+fn tightened() {
+    let old = get_noise_20();
+    let mut new = get_noise_20();
+    let p = "/extraCityObjects/+NoiseRoadSegment/allOf/1";
+    new.pointer_mut(&format!("{}/required", p))
+        .unwrap()
+        .as_array_mut()
+        .unwrap()
+        .push(json!("attributes"));
+    let re = diff(&old, &new);
+    assert_eq!(re.changes.len(), 1);
+    assert!(re.is_breaking());
+    assert_eq!(re.changes[0].path, format!("{}/required", p));
+
+    let mut new = get_noise_20();
+    new["definitions"]["measure"]["properties"]["uom"]["enum"] = json!(["dB"]);
+    let re = diff(&old, &new);
+    assert!(re.is_breaking());
+
+    let mut new = get_noise_20();
+    new["definitions"]["measure"]["properties"]["value"]["minimum"] = json!(0);
+    assert!(diff(&old, &new).is_breaking());
+}
+
+#[test]
+fn loosened() {
+    let mut old = get_noise_20();
+    old["definitions"]["measure"]["properties"]["uom"]["enum"] = json!(["dB"]);
+    let mut new = old.clone();
+    new["definitions"]["measure"]["properties"]["uom"]["enum"] = json!(["dB", "dB(A)"]);
+    let re = diff(&old, &new);
+    assert_eq!(re.changes.len(), 1);
+    assert!(!re.is_breaking());
+
+    let p = "/extraCityObjects/+NoiseRoadSegment/allOf/1/required";
+    new.pointer_mut(p)
+        .unwrap()
+        .as_array_mut()
+        .unwrap()
+        .retain(|x| x != "parents");
+    assert!(!diff(&old, &new).is_breaking());
+}
+
+#[test]
+fn added_property() {
+    //-- "measure" doesn't allow additional properties
+    let old = get_noise_20();
+    let mut new = get_noise_20();
+    new["definitions"]["measure"]["properties"]["method"] = json!({"type": "string"});
+    let re = diff(&old, &new);
+    assert_eq!(re.changes.len(), 1);
+    assert!(!re.is_breaking());
+
+    //-- otherwise the values of "method" were allowed and not constrained
+    let mut old = get_noise_20();
+    old["definitions"]["measure"]
+        .as_object_mut()
+        .unwrap()
+        .remove("additionalProperties");
+    let mut new = old.clone();
+    new["definitions"]["measure"]["properties"]["method"] = json!({"type": "string"});
+    let re = diff(&old, &new);
+    assert_eq!(re.changes.len(), 1);
+    assert!(re.is_breaking());
+    assert_eq!(re.changes[0].path, "/definitions/measure/properties/method");
+}
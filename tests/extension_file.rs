@@ -0,0 +1,82 @@
+use serde_json::json;
+use serde_json::Value;
+
+fn get_noise_20() -> Value {
+    let s = std::fs::read_to_string("schemas/extensions/20/noise.ext.json").unwrap();
+    serde_json::from_str(&s).unwrap()
+}
+
+#[test]
+fn valid_extensions() {
+    for f in [
+        "schemas/extensions/20/noise.ext.json",
+        "schemas/extensions/20/generic.ext.json",
+        "schemas/extensions/20/potato.ext.json",
+        "schemas/extensions/11/noise.ext.json",
+    ] {
+        let s = std::fs::read_to_string(f).unwrap();
+        let re = cjval::validate_extension_str(&s);
+        assert!(re.is_valid(), "{}: {:?}", f, re.errors);
+    }
+}
+
+#[test]
+fn invalid_json() {
+    let s = std::fs::read_to_string("schemas/extensions/20/noise2.ext.json").unwrap();
+    let re = cjval::validate_extension_str(&s);
+    assert!(!re.is_valid());
+    assert_eq!(re.errors[0].check, "json_syntax");
+    let re = cjval::validate_extension_str(r#"{"type":"CityJSONExtension"}"#);
+    assert_eq!(re.errors[0].path, "/versionCityJSON");
+}
+
+#[test]
+fn refs() {
+    //-- the 1.1 GeometryInstance is in geomtemplates.schema.json
+    let s = std::fs::read_to_string("schemas/extensions/11/generic.ext.json").unwrap();
+    let re = cjval::validate_extension_str(&s);
+    assert_eq!(re.errors.len(), 1);
+    assert_eq!(re.errors[0].check, "refs");
+    assert!(re.errors[0].path.ends_with("/oneOf/7/$ref"));
+
+    let mut j = get_noise_20();
+    j["definitions"].as_object_mut().unwrap().remove("measure");
+    let re = cjval::validate_extension_str(&j.to_string());
+    assert!(!re.is_valid());
+    assert!(re.errors.iter().all(|e| e.check == "refs"));
+    assert!(re.errors_by_check().contains_key("refs"));
+}
+
+#[test]
+fn names() {
+    let mut j = get_noise_20();
+    j["extraAttributes"]["Building"]["noise"] = json!({"type": "string"});
+    let re = cjval::validate_extension_str(&j.to_string());
+    let names = &re.errors_by_check()["names"];
+    assert_eq!(names.len(), 1);
+    assert!(names[0].ends_with("[path:/extraAttributes/Building/noise]"));
+}
+
+#[test]
+fn compile() {
+    let mut j = get_noise_20();
+    j["extraRootProperties"] = json!({"+census": {"type": "integer", "minimum": "zero"}});
+    let re = cjval::validate_extension_str(&j.to_string());
+    assert!(!re.is_valid());
+    assert_eq!(re.errors[0].check, "compile");
+    assert_eq!(re.errors[0].path, "/extraRootProperties/+census");
+}
+
+#[test]
+fn extension_10() {
+    let s = std::fs::read_to_string("schemas/extensions/10/noise.ext.json").unwrap();
+    let re = cjval::validate_extension_str(&s);
+    assert!(re.is_valid());
+    assert_eq!(re.version_cityjson.as_deref(), Some("1.0"));
+    let s = s.replace(
+        "../geomprimitives.schema.json#/MultiLineString",
+        "../geomprimitives.schema.json#/MultiLine",
+    );
+    let re = cjval::validate_extension_str(&s);
+    assert_eq!(re.errors_by_check()["refs"].len(), 3);
+}
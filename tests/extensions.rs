@@ -174,3 +174,183 @@ fn extension_20() {
     assert!(re["schema"].is_valid());
     assert!(!re["extensions"].is_valid());
 }
+
+#[test]
+fn extension_errors_attributed() {
+    let mut j = get_minimal_20();
+    j["CityObjects"]["un"]["attributes"] = json!({"my-attribute": 12});
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let s = std::fs::read_to_string("schemas/extensions/20/generic.ext.json").unwrap();
+    let _ = v.add_one_extension_from_str(&s);
+    let re = v.validate();
+    assert!(!re["extensions"].is_valid());
+    assert!(re["extensions"]
+        .get_errors()
+        .iter()
+        .all(|e| e.ends_with("[Extension:Generic]")));
+    assert_eq!(
+        v.get_extension_definitions()["+GenericCityObject"],
+        "Generic"
+    );
+}
+
+#[test]
+fn extension_conflicts() {
+    let j = get_minimal_20();
+    let s = std::fs::read_to_string("schemas/extensions/20/generic.ext.json").unwrap();
+    //-- the same Extension twice is not a conflict
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let _ = v.add_one_extension_from_str(&s);
+    let _ = v.add_one_extension_from_str(&s);
+    assert!(v.validate()["extensions"].is_valid());
+    //-- another Extension defining +GenericCityObject differently
+    let mut jext: Value = serde_json::from_str(&s).unwrap();
+    jext["name"] = json!("Generic2");
+    jext["extraCityObjects"]["+GenericCityObject"]["allOf"][1]["required"] =
+        json!(["type", "attributes"]);
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let _ = v.add_one_extension_from_str(&s);
+    let _ = v.add_one_extension_from_str(&jext.to_string());
+    let re = v.validate();
+    assert!(!re["extensions"].is_valid());
+    assert!(re["extensions"].get_errors()[0].contains("'Generic' and 'Generic2'"));
+}
+
+#[test]
+fn extension_10() {
+    let sdata = std::fs::read_to_string("data/noise10.city.json").unwrap();
+    let mut v: CJValidator = CJValidator::from_str(&sdata);
+    let re = v.validate();
+    assert!(re["schema"].is_valid());
+    assert!(!re["extensions"].is_valid());
+
+    let sschema = std::fs::read_to_string("schemas/extensions/10/noise.ext.json").unwrap();
+    let _ = v.add_one_extension_from_str(&sschema);
+    let re = v.validate();
+    assert!(re["extensions"].is_valid());
+
+    //-- the schemas of the core are used
+    let mut j: Value = serde_json::from_str(&sdata).unwrap();
+    j["CityObjects"]["trois"]["parents"] = json!("deux");
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let _ = v.add_one_extension_from_str(&sschema);
+    assert!(!v.validate()["extensions"].is_valid());
+
+    //-- a v1.1 Extension cannot be used for a v1.0 file
+    let sschema = std::fs::read_to_string("schemas/extensions/11/noise.ext.json").unwrap();
+    let mut v: CJValidator = CJValidator::from_str(&sdata);
+    let _ = v.add_one_extension_from_str(&sschema);
+    let re = v.validate();
+    assert!(!re["extensions"].is_valid());
+    assert!(re["extensions"].get_errors()[0].contains("1.0"));
+}
+
+fn get_barrier_20() -> (Value, Value) {
+    let j = json!({
+      "type": "CityJSON",
+      "version": "2.0",
+      "transform": {
+        "scale": [0.001, 0.001, 0.001],
+        "translate": [1.0, 1.0, 1.0]
+      },
+      "extensions": {
+        "Barrier": {
+          "url": "https://someurl.org/barrier.ext.json",
+          "version": "1.0"
+        }
+      },
+      "CityObjects": {
+        "un": {
+          "type": "Building",
+          "attributes": {
+            "+barrier-height": {"value": 2.5, "uom": "m"}
+          }
+        }
+      },
+      "vertices": []
+    });
+    //-- reuses a definition of the Noise Extension
+    let jext = json!({
+      "type": "CityJSONExtension",
+      "name": "Barrier",
+      "uri": "https://someurl.org/barrier.ext.json",
+      "version": "1.0",
+      "versionCityJSON": "2.0",
+      "description": "Barriers",
+      "extraAttributes": {
+        "Building": {
+          "+barrier-height": {
+            "$ref": "https://www.cityjson.org/tutorials/files/noise.ext.json#/definitions/measure"
+          }
+        }
+      },
+      "extraCityObjects": {},
+      "extraRootProperties": {},
+      "extraSemanticSurfaces": {}
+    });
+    (j, jext)
+}
+
+#[test]
+fn extension_cross_refs() {
+    let (mut j, jext) = get_barrier_20();
+    let snoise = std::fs::read_to_string("schemas/extensions/20/noise.ext.json").unwrap();
+    //-- the Noise Extension is not loaded: an error, not a panic
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let _ = v.add_one_extension_from_str(&jext.to_string());
+    let re = v.validate();
+    assert!(!re["extensions"].is_valid());
+    assert!(re["extensions"].get_errors()[0].contains("cannot be resolved"));
+
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let _ = v.add_one_extension_from_str(&jext.to_string());
+    let _ = v.add_one_extension_from_str(&snoise);
+    assert!(v.validate()["extensions"].is_valid());
+
+    j["CityObjects"]["un"]["attributes"]["+barrier-height"] = json!({"value": "high"});
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let _ = v.add_one_extension_from_str(&jext.to_string());
+    let _ = v.add_one_extension_from_str(&snoise);
+    assert!(!v.validate()["extensions"].is_valid());
+}
+
+#[test]
+fn extension_cross_refs_file_name() {
+    let (j, jext) = get_barrier_20();
+    let snoise = std::fs::read_to_string("schemas/extensions/20/noise.ext.json").unwrap();
+    let mut noise: Value = serde_json::from_str(&snoise).unwrap();
+    //-- only one "noise.ext.json" is loaded: it is used
+    noise["uri"] = json!("https://a.org/noise.ext.json");
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let _ = v.add_one_extension_from_str(&jext.to_string());
+    let _ = v.add_one_extension_from_str(&noise.to_string());
+    assert!(v.validate()["extensions"].is_valid());
+
+    //-- 2 are loaded: it is ambiguous
+    noise["uri"] = json!("https://b.org/noise.ext.json");
+    let _ = v.add_one_extension_from_str(&noise.to_string());
+    let re = v.validate();
+    assert!(!re["extensions"].is_valid());
+    assert!(re["extensions"].get_errors()[0].contains("several Extensions"));
+
+    //-- unless one has the URL of the "$ref"
+    let _ = v.add_one_extension_from_str(&snoise);
+    assert!(v.validate()["extensions"].is_valid());
+}
+
+#[test]
+fn extension_cross_refs_resolver() {
+    let (j, jext) = get_barrier_20();
+    let snoise = std::fs::read_to_string("schemas/extensions/20/noise.ext.json").unwrap();
+    let mut resolver = cjval::MemoryResolver::new();
+    resolver.insert("Barrier", &jext.to_string());
+    resolver.insert(
+        "https://www.cityjson.org/tutorials/files/noise.ext.json",
+        &snoise,
+    );
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let statuses = v.resolve_extensions(&resolver);
+    assert_eq!(statuses.len(), 2);
+    assert!(statuses.values().all(|s| s.is_ok()));
+    assert!(v.validate()["extensions"].is_valid());
+}
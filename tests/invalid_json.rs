@@ -115,3 +115,20 @@ fn non_json() {
     let re = v.validate();
     assert!(!re["json_syntax"].is_valid());
 }
+
+#[test]
+fn from_reader() {
+    let f = std::fs::File::open("data/cube.city.json").unwrap();
+    let v = CJValidator::from_reader(std::io::BufReader::new(f));
+    let s = std::fs::read_to_string("data/cube.city.json").unwrap();
+    let v2 = CJValidator::from_str(&s);
+    let re = v.validate();
+    let re2 = v2.validate();
+    for (criterion, summ) in re.iter() {
+        assert_eq!(summ.is_valid(), re2[criterion].is_valid());
+    }
+
+    let v = CJValidator::from_reader("{\"type\": \"CityJSON\",".as_bytes());
+    let re = v.validate();
+    assert!(!re["json_syntax"].is_valid());
+}
@@ -0,0 +1,122 @@
+use cjval::{CJValidator, Location};
+use serde_json::json;
+use serde_json::Value;
+
+fn get_data() -> Value {
+    json!({
+      "type": "CityJSON",
+      "version": "2.0",
+      "transform": {
+        "scale": [0.001, 0.001, 0.001],
+        "translate": [0.0, 0.0, 0.0]
+      },
+      "CityObjects": {
+        "b1": {
+          "type": "Building",
+          "attributes": {"alias": "Ålesund", "height": 12.1},
+          "geometry": [{
+            "type": "MultiSurface",
+            "lod": "1",
+            "boundaries": [[[0, 1, 2]], [[0, 2, 999]]]
+          }]
+        }
+      },
+      "vertices": [[0, 0, 0], [1000, 0, 0], [0, 1000, 0]]
+    })
+}
+
+//-- the location of the n-th occurrence of the pattern
+fn location_of(s: &str, pat: &str, n: usize) -> Location {
+    let offset = s.match_indices(pat).nth(n).unwrap().0;
+    let before = &s[..offset];
+    let linestart = before.rfind('\n').map_or(0, |i| i + 1);
+    Location {
+        offset,
+        line: before.matches('\n').count() + 1,
+        column: before[linestart..].chars().count() + 1,
+    }
+}
+
+#[test]
+fn cityobject() {
+    let s = serde_json::to_string_pretty(&get_data()).unwrap();
+    let re = CJValidator::from_str_with_locations(&s).validate();
+    let d = &re["wrong_vertex_index"].get_diagnostics()[0];
+    assert_eq!(d.location, Some(location_of(&s, "\"b1\"", 0)));
+    assert!(d.location.unwrap().line > 1);
+}
+
+#[test]
+fn pointer() {
+    let mut j = get_data();
+    j["CityObjects"]["b1"]["geometry"][0]["lod"] = json!(1.5);
+    let s = serde_json::to_string_pretty(&j).unwrap();
+    let re = CJValidator::from_str_with_locations(&s).validate();
+    let d = &re["schema"].get_diagnostics()[0];
+    assert_eq!(d.pointer.as_deref(), Some("/CityObjects/b1"));
+    assert_eq!(d.location, Some(location_of(&s, "\"b1\"", 0)));
+}
+
+#[test]
+fn attribute() {
+    let mut j = get_data();
+    j["CityObjects"]["b1"]["geometry"][0]["boundaries"][1][0][2] = json!(1);
+    //-- the column is in characters
+    let s = j.to_string();
+    let mut v = CJValidator::from_str_with_locations(&s);
+    v.add_attribute_schema(
+        "Building",
+        &json!({"properties": {"height": {"type": "integer"}}}),
+    )
+    .unwrap();
+    let re = v.validate();
+    let d = &re["attribute_schemas"].get_diagnostics()[0];
+    let l = location_of(&s, "\"height\"", 0);
+    assert_eq!(d.location, Some(l));
+    assert_eq!(l.line, 1);
+    assert_eq!(l.column, l.offset);
+}
+
+#[test]
+fn cjseq_feature() {
+    let s = std::fs::read_to_string("data/cube_20.jsonl").unwrap();
+    let mut lines = s.lines();
+    let mut v = CJValidator::from_str_with_locations(lines.next().unwrap());
+    let mut f: Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    let id = f["id"].as_str().unwrap().to_string();
+    f["CityObjects"][&id]["type"] = json!("GenericCityObject");
+    f["CityObjects"][&id]["geometry"][0]["boundaries"][0][0][0][0] = json!(9999);
+    let l = f.to_string();
+    v.from_str_cjfeature_at(&l, 7).unwrap();
+    let re = v.validate();
+    let d = &re["wrong_vertex_index"].get_diagnostics()[0];
+    let mut expected = location_of(&l, &format!("\"{}\"", id), 0);
+    expected.line = 7;
+    assert_eq!(d.location, Some(expected));
+}
+
+#[test]
+fn not_kept() {
+    let s = serde_json::to_string_pretty(&get_data()).unwrap();
+    let re = CJValidator::from_str(&s).validate();
+    let d = &re["wrong_vertex_index"].get_diagnostics()[0];
+    assert_eq!(d.cityobject.as_deref(), Some("b1"));
+    assert_eq!(d.location, None);
+    let re = CJValidator::from_reader(s.as_bytes()).validate();
+    assert_eq!(re["wrong_vertex_index"].get_diagnostics()[0].location, None);
+    let re = CJValidator::from_reader_with_locations(s.as_bytes()).validate();
+    let d = &re["wrong_vertex_index"].get_diagnostics()[0];
+    assert_eq!(d.location, Some(location_of(&s, "\"b1\"", 0)));
+}
+
+#[test]
+fn escaped_key() {
+    let mut j = get_data();
+    let co = j["CityObjects"]["b1"].take();
+    j["CityObjects"] = json!({"b~/1": co});
+    let s = serde_json::to_string_pretty(&j).unwrap();
+    let re = CJValidator::from_str_with_locations(&s).validate();
+    let d = &re["wrong_vertex_index"].get_diagnostics()[0];
+    assert_eq!(d.cityobject.as_deref(), Some("b~/1"));
+    assert_eq!(d.location, Some(location_of(&s, "\"b~/1\"", 0)));
+}
@@ -0,0 +1,176 @@
+use cjval::{CJValidator, Severity, ValidationOptions};
+use serde_json::json;
+use serde_json::Value;
+
+fn get_data() -> Value {
+    json!({
+        "type": "CityJSON",
+        "version": "1.1",
+        "CityObjects": {
+            "LondonTower": { "type": "Building" }
+        },
+        "vertices": [
+            [0, 0, 0],
+            [1000, 0, 0],
+            [1000, 1000, 0],
+            [1000, 0, 0]
+        ],
+        "transform": {
+            "scale": [0.001, 0.001, 0.001],
+            "translate": [0.0, 0.0, 0.0]
+        }
+    })
+}
+
+#[test]
+fn default_rules() {
+    let v: CJValidator = CJValidator::from_str(&get_data().to_string());
+    let re = v.validate();
+    assert!(re["duplicate_vertices"].has_errors());
+    assert!(re["duplicate_vertices"].is_warning());
+    assert!(v.is_valid());
+    assert_eq!(v.get_options().to_string(), "default");
+}
+
+#[test]
+fn change_severity() {
+    let mut opts = ValidationOptions::new();
+    opts.set("duplicate_vertices", Severity::Error).unwrap();
+    opts.set("unused_vertices", Severity::Off).unwrap();
+    let mut v: CJValidator = CJValidator::from_str(&get_data().to_string());
+    v.set_options(opts);
+    let re = v.validate();
+    assert!(re["duplicate_vertices"].has_errors());
+    assert!(!re["duplicate_vertices"].is_warning());
+    assert!(re["unused_vertices"].is_disabled());
+    assert!(!re["unused_vertices"].has_errors());
+    assert!(!v.is_valid());
+    assert_eq!(
+        v.get_options().to_string(),
+        "default (duplicate_vertices=error, unused_vertices=off)"
+    );
+}
+
+#[test]
+fn warning_as_error_after_errors() {
+    //-- duplicate vertices and a geometry error that is valid for the schema
+    let mut j = get_data();
+    j["CityObjects"]["LondonTower"]["geometry"] = json!([{
+        "type": "MultiSurface",
+        "lod": "1",
+        "boundaries": [[[0, 1, 2, 99]]]
+    }]);
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let re = v.validate();
+    assert!(re["wrong_vertex_index"].has_errors());
+    assert!(!re["duplicate_vertices"].has_errors());
+    let mut opts = ValidationOptions::new();
+    opts.set("duplicate_vertices", Severity::Error).unwrap();
+    v.set_options(opts);
+    let re = v.validate();
+    assert!(re["wrong_vertex_index"].has_errors());
+    assert!(re["duplicate_vertices"].has_errors());
+    assert!(!re["duplicate_vertices"].is_warning());
+    assert!(!re["duplicate_vertices"].may_be_cascading());
+}
+
+#[test]
+fn error_as_warning() {
+    let mut j = get_data();
+    j["CityObjects"]["LondonTower"]["type"] = json!("Buildingg");
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    assert!(!v.is_valid());
+    let mut opts = ValidationOptions::new();
+    opts.set("schema", Severity::Warning).unwrap();
+    v.set_options(opts);
+    let re = v.validate();
+    assert!(re["schema"].has_errors());
+    assert!(re["schema"].is_warning());
+    //-- the other checks are performed
+    assert!(re["wrong_vertex_index"].is_valid());
+    assert!(v.is_valid());
+}
+
+#[test]
+fn unknown_check() {
+    let mut opts = ValidationOptions::new();
+    assert!(opts.set("json_syntax", Severity::Off).is_err());
+    assert_eq!(opts, ValidationOptions::default());
+    //-- it may be an added check, the validation reports it otherwise
+    opts.set("duplicate_vertex", Severity::Error).unwrap();
+    let mut v: CJValidator = CJValidator::from_str(&get_data().to_string());
+    v.set_options(opts);
+    assert!(!v
+        .get_check_names()
+        .contains(&"duplicate_vertex".to_string()));
+    let re = v.validate();
+    assert!(re["options"].has_errors());
+    assert!(re["options"].get_errors()[0].starts_with("Unknown check 'duplicate_vertex'"));
+    assert!(re["duplicate_vertices"].is_warning());
+    assert!(!v.is_valid());
+    //-- not with the default options
+    let v: CJValidator = CJValidator::from_str(&get_data().to_string());
+    assert!(!v.validate().contains_key("options"));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn from_toml() {
+    let opts = ValidationOptions::from_toml_str(
+        r#"
+        name = "delivery-contract"
+        exhaustive = true
+        [rules]
+        duplicate_vertices = "error"
+        unused_vertices = "off"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(opts.name(), "delivery-contract");
+    assert!(opts.is_exhaustive());
+    assert_eq!(opts.severity("duplicate_vertices"), Severity::Error);
+    assert!(!opts.is_enabled("unused_vertices"));
+    assert_eq!(opts.severity("schema"), Severity::Error);
+    assert!(ValidationOptions::from_toml_str("[rules]\nschema = \"fatal\"").is_err());
+    let opts = ValidationOptions::from_toml_str("[rules]\nfoo = \"off\"").unwrap();
+    assert!(!opts.is_enabled("foo"));
+    assert!(ValidationOptions::from_toml_str("[rulez]\nschema = \"off\"").is_err());
+}
+
+#[test]
+fn exhaustive() {
+    let mut j = get_data();
+    j["CityObjects"]["LondonTower"]["type"] = json!("Buildingg");
+    j["CityObjects"]["LondonTower"]["children"] = json!(["nope"]);
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    let re = v.validate();
+    assert!(re["schema"].has_errors());
+    assert!(!re["parents_children_consistency"].has_errors());
+    assert!(!re["duplicate_vertices"].has_errors());
+
+    let mut opts = ValidationOptions::new();
+    opts.set_exhaustive(true);
+    v.set_options(opts);
+    let re = v.validate();
+    assert!(re["schema"].has_errors());
+    assert!(!re["schema"].may_be_cascading());
+    assert!(re["parents_children_consistency"].has_errors());
+    assert!(re["parents_children_consistency"].may_be_cascading());
+    assert!(re["duplicate_vertices"].has_errors());
+    assert!(re["duplicate_vertices"].may_be_cascading());
+    assert!(re["wrong_vertex_index"].is_valid());
+    assert!(!re["wrong_vertex_index"].may_be_cascading());
+    assert_eq!(v.get_options().to_string(), "default (exhaustive)");
+}
+
+#[test]
+fn exhaustive_without_errors() {
+    let mut opts = ValidationOptions::new();
+    opts.set_exhaustive(true);
+    let mut v: CJValidator = CJValidator::from_str(&get_data().to_string());
+    v.set_options(opts);
+    let re = v.validate();
+    assert!(re["duplicate_vertices"].has_errors());
+    assert!(!re["duplicate_vertices"].may_be_cascading());
+    assert!(v.is_valid());
+}
@@ -0,0 +1,189 @@
+use cjval::{CJValidator, Profile, Severity, ValidationOptions};
+use serde_json::json;
+use serde_json::Value;
+
+fn get_data() -> Value {
+    let solid = |lod: &str| {
+        json!({
+          "type": "Solid",
+          "lod": lod,
+          "boundaries": [[ [[0, 1, 2, 3]], [[4, 5, 0, 3]], [[5, 6, 1, 0]], [[6, 7, 2, 1]], [[3, 2, 7, 4]], [[7, 6, 5, 4]] ]]
+        })
+    };
+    json!({
+      "type": "CityJSON",
+      "version": "2.0",
+      "transform": {
+        "scale": [0.001, 0.001, 0.001],
+        "translate": [0.0, 0.0, 0.0]
+      },
+      "metadata": {
+        "referenceSystem": "https://www.opengis.net/def/crs/EPSG/0/7415"
+      },
+      "CityObjects": {
+        "b1": {
+          "type": "Building",
+          "attributes": {"identificatie": "NL.1", "status": "Pand in gebruik"},
+          "geometry": [solid("1.2"), solid("2.2")]
+        },
+        "b2": {
+          "type": "Building",
+          "attributes": {"identificatie": "NL.2", "status": "Bouw gestart"},
+          "geometry": [solid("2.2")]
+        }
+      },
+      "vertices": [[2000, 1000, 1000], [1000, 2000, 1000], [0, 1000, 1000], [1000, 0, 1000], [1000, 0, 0], [2000, 1000, 0], [1000, 2000, 0], [0, 1000, 0]]
+    })
+}
+
+fn get_profile() -> Profile {
+    let p = json!({
+      "name": "delivery",
+      "properties": {
+        "/metadata/referenceSystem": {
+          "required": true,
+          "allowed": ["https://www.opengis.net/def/crs/EPSG/0/7415"]
+        }
+      },
+      "cityobjects": {
+        "Building": {
+          "geometry": [{"type": "Solid", "lod": "2.2"}],
+          "attributes": {
+            "identificatie": {"required": true, "type": "string", "unique": true},
+            "status": {"allowed": ["Pand in gebruik", "Bouw gestart"]}
+          }
+        }
+      }
+    });
+    Profile::from_json_str(&p.to_string()).unwrap()
+}
+
+fn validate_profile(j: &Value) -> Vec<String> {
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    v.set_profile(get_profile());
+    let re = v.validate();
+    assert!(re["schema"].is_valid());
+    re["profile"].get_errors().clone()
+}
+
+#[test]
+fn valid() {
+    let j = get_data();
+    assert!(validate_profile(&j).is_empty());
+    //-- without a profile the check is not performed
+    let v: CJValidator = CJValidator::from_str(&j.to_string());
+    let re = v.validate();
+    assert!(!re["profile"].is_valid());
+    assert!(!re["profile"].has_errors());
+    assert!(v.is_valid());
+}
+
+#[test]
+fn geometry() {
+    let mut j = get_data();
+    j["CityObjects"]["b2"]["geometry"][0]["lod"] = json!("2.1");
+    let errs = validate_profile(&j);
+    assert_eq!(errs.len(), 1);
+    assert!(errs[0].contains("#b2"));
+    assert!(errs[0].contains("LoD 2.2"));
+}
+
+#[test]
+fn attributes() {
+    let mut j = get_data();
+    j["CityObjects"]["b2"]["attributes"]["identificatie"] = json!("NL.1");
+    assert!(validate_profile(&j)[0].contains("not unique"));
+    j["CityObjects"]["b2"]["attributes"]["identificatie"] = json!(12);
+    assert!(validate_profile(&j)[0].contains("not a string"));
+    j["CityObjects"]["b2"]["attributes"] = json!({"status": "Gesloopt"});
+    let errs = validate_profile(&j);
+    assert_eq!(errs.len(), 2);
+    assert!(errs[0].contains("'identificatie'") && errs[0].contains("missing"));
+    assert!(errs[1].contains("not allowed"));
+}
+
+#[test]
+fn properties() {
+    let mut j = get_data();
+    j["metadata"]["referenceSystem"] = json!("https://www.opengis.net/def/crs/EPSG/0/28992");
+    assert!(validate_profile(&j)[0].contains("/metadata/referenceSystem"));
+    j.as_object_mut().unwrap().remove("metadata");
+    assert!(validate_profile(&j)[0].contains("missing"));
+}
+
+#[test]
+fn severity() {
+    let mut j = get_data();
+    j["CityObjects"]["b2"]["geometry"][0]["lod"] = json!("2.1");
+    let mut v: CJValidator = CJValidator::from_str(&j.to_string());
+    v.set_profile(get_profile());
+    assert!(!v.is_valid());
+    let mut opts = ValidationOptions::new();
+    opts.set("profile", Severity::Warning).unwrap();
+    v.set_options(opts);
+    assert!(v.validate()["profile"].is_warning());
+    assert!(v.is_valid());
+}
+
+#[test]
+fn wrong_profile() {
+    assert!(Profile::from_json_str(r#"{"cityobject": {}}"#).is_err());
+    assert!(Profile::from_json_str(r#"{"cityobjects": {"Building": {"lod": "2.2"}}}"#).is_err());
+    assert!(Profile::from_json_str(r#"{"properties": {"/metadata": {"type": "text"}}}"#).is_err());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn from_toml() {
+    let p = Profile::from_toml_str(
+        r#"
+        name = "delivery"
+
+        [properties."/metadata/referenceSystem"]
+        required = true
+        allowed = ["https://www.opengis.net/def/crs/EPSG/0/7415"]
+
+        [cityobjects.Building]
+        geometry = [{ type = "Solid", lod = "2.2" }]
+
+        [cityobjects.Building.attributes.identificatie]
+        required = true
+        type = "string"
+        unique = true
+
+        [cityobjects.Building.attributes.status]
+        allowed = ["Pand in gebruik", "Bouw gestart"]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(p, get_profile());
+}
+
+#[test]
+fn unique_in_cjseq() {
+    let j = get_data();
+    let mut first = j.clone();
+    first["CityObjects"] = json!({});
+    first["vertices"] = json!([]);
+    let feature = |id: &str, identificatie: &str| {
+        let mut co = j["CityObjects"]["b2"].clone();
+        co["attributes"]["identificatie"] = json!(identificatie);
+        json!({
+          "type": "CityJSONFeature",
+          "id": id,
+          "CityObjects": {id: co},
+          "vertices": j["vertices"]
+        })
+        .to_string()
+    };
+    let mut v: CJValidator = CJValidator::from_str(&first.to_string());
+    v.set_profile(get_profile());
+    v.from_str_cjfeature(&feature("b1", "NL.1")).unwrap();
+    assert!(v.validate()["profile"].is_valid());
+    v.from_str_cjfeature(&feature("b2", "NL.2")).unwrap();
+    assert!(v.validate()["profile"].is_valid());
+    v.from_str_cjfeature(&feature("b3", "NL.1")).unwrap();
+    let re = v.validate();
+    assert!(!re["profile"].is_valid());
+    assert!(re["profile"].get_errors()[0].contains("also used by #b1"));
+}
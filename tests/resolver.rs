@@ -0,0 +1,186 @@
+use cjval::CJValidator;
+use cjval::{CacheResolver, ExtensionRef, ExtensionResolver, LocalDirResolver, MemoryResolver};
+use std::time::Duration;
+
+fn get_noise() -> CJValidator {
+    let s = std::fs::read_to_string("data/noise1.city.json").unwrap();
+    CJValidator::from_str(&s)
+}
+
+#[test]
+fn memory_resolver() {
+    let mut val = get_noise();
+    let sext = std::fs::read_to_string("schemas/extensions/11/noise.ext.json").unwrap();
+    let mut resolver = MemoryResolver::new();
+    resolver.insert(
+        "https://raw.githubusercontent.com/cityjson/specs/main/extensions/Noise/noise3.ext.json",
+        &sext,
+    );
+    let statuses = val.resolve_extensions(&resolver);
+    assert_eq!(statuses.len(), 1);
+    assert!(statuses["Noise"].is_ok());
+    assert_eq!(statuses["Noise"].version, "1.0");
+    assert!(val.validate()["extensions"].is_valid());
+}
+
+#[test]
+fn missing_extension() {
+    let mut val = get_noise();
+    let statuses = val.resolve_extensions(&MemoryResolver::new());
+    assert!(!statuses["Noise"].is_ok());
+}
+
+#[test]
+fn local_dir_resolver() {
+    //-- "noise3.ext.json" is not in the dir, "Noise.ext.json" neither
+    let mut val = get_noise();
+    let statuses = val.resolve_extensions(&LocalDirResolver::new("schemas/extensions/11"));
+    assert!(!statuses["Noise"].is_ok());
+    let ext = ExtensionRef {
+        name: "Noise".to_string(),
+        url: "https://someurl.org/noise.ext.json".to_string(),
+        version: "1.0".to_string(),
+    };
+    assert_eq!(ext.file_name(), "noise.ext.json");
+    let re = LocalDirResolver::new("schemas/extensions/11").resolve(&ext);
+    assert!(re.is_ok());
+}
+
+#[test]
+fn cache_resolver() {
+    let dir = std::env::temp_dir().join(format!("cjval-cache-{}", std::process::id()));
+    let ext = ExtensionRef {
+        name: "Noise".to_string(),
+        url: "https://someurl.org/noise.ext.json".to_string(),
+        version: "1.0".to_string(),
+    };
+    let mut mem = MemoryResolver::new();
+    mem.insert("Noise", "{}");
+    let cache = CacheResolver::new(&dir, mem);
+    assert_eq!(cache.resolve(&ext).unwrap(), "{}");
+    //-- 2nd time it's read from the cache, the inner resolver is empty
+    let cache = CacheResolver::new(&dir, MemoryResolver::new());
+    assert_eq!(cache.resolve(&ext).unwrap(), "{}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cache_resolver_expired() {
+    let dir = std::env::temp_dir().join(format!("cjval-cache-expired-{}", std::process::id()));
+    let ext = ExtensionRef {
+        name: "Noise".to_string(),
+        url: "https://someurl.org/noise.ext.json".to_string(),
+        version: "1.0".to_string(),
+    };
+    let mut mem = MemoryResolver::new();
+    mem.insert("Noise", "{}");
+    let cache = CacheResolver::new(&dir, mem);
+    assert_eq!(cache.resolve(&ext).unwrap(), "{}");
+    //-- a newer Extension with the same URL and version
+    let mut mem = MemoryResolver::new();
+    mem.insert("Noise", "{\"v\": 2}");
+    let mut cache = CacheResolver::new(&dir, mem);
+    assert_eq!(cache.resolve(&ext).unwrap(), "{}");
+    cache.set_max_age(Some(Duration::from_secs(3600)));
+    assert_eq!(cache.resolve(&ext).unwrap(), "{}");
+    cache.set_max_age(Some(Duration::ZERO));
+    assert_eq!(cache.resolve(&ext).unwrap(), "{\"v\": 2}");
+    //-- the expired copy is used if the Extension cannot be resolved
+    let mut cache = CacheResolver::new(&dir, MemoryResolver::new());
+    cache.set_max_age(Some(Duration::ZERO));
+    assert_eq!(cache.resolve(&ext).unwrap(), "{\"v\": 2}");
+    cache.remove(&ext).unwrap();
+    assert!(cache.resolve(&ext).is_err());
+    assert!(cache.remove(&ext).is_ok());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn extension_version_mismatch() {
+    //-- declared "1.0", the file is "1.1.0"
+    let mut val = get_noise();
+    let sext = std::fs::read_to_string("schemas/extensions/11/noise.ext.json").unwrap();
+    let mut resolver = MemoryResolver::new();
+    resolver.insert("Noise", &sext);
+    let statuses = val.resolve_extensions(&resolver);
+    assert_eq!(statuses["Noise"].ext_version.as_deref(), Some("1.1.0"));
+    let re = val.validate();
+    assert!(re["extensions"].is_valid());
+    assert!(!re["extension_versions"].is_valid());
+    assert!(re["extension_versions"].is_warning());
+}
+
+#[test]
+fn select_version() {
+    let mut val = get_noise();
+    let sext = std::fs::read_to_string("schemas/extensions/11/noise.ext.json").unwrap();
+    let mut j: serde_json::Value = serde_json::from_str(&sext).unwrap();
+    j["version"] = serde_json::json!("1.0.3");
+    let mut resolver = MemoryResolver::new();
+    resolver.insert("Noise", &sext);
+    resolver.insert_version("Noise", "1.0", &j.to_string());
+    let statuses = val.resolve_extensions(&resolver);
+    assert_eq!(statuses["Noise"].ext_version.as_deref(), Some("1.0.3"));
+    assert!(val.validate()["extension_versions"].is_valid());
+}
+
+#[test]
+fn matches_version() {
+    let ext = ExtensionRef {
+        name: "Noise".to_string(),
+        url: "https://someurl.org/noise.ext.json".to_string(),
+        version: "1.0".to_string(),
+    };
+    assert!(ext.matches_version("1.0"));
+    assert!(ext.matches_version("1.0.2"));
+    assert!(!ext.matches_version("1.1.0"));
+    assert!(!ext.matches_version("2.0"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn http_timeout() {
+    //-- a server that accepts the connection but never answers
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let ext = ExtensionRef {
+        name: "Noise".to_string(),
+        url: format!("http://{}/noise.ext.json", listener.local_addr().unwrap()),
+        version: "1.0".to_string(),
+    };
+    let resolver = cjval::HttpResolver::with_timeout(std::time::Duration::from_millis(200));
+    let start = std::time::Instant::now();
+    assert!(resolver.resolve(&ext).is_err());
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+}
+
+#[test]
+fn local_dir_resolver_outside_dir() {
+    let resolver = LocalDirResolver::new("schemas/extensions/11");
+    let url = "https://someurl.org/noise.ext.json";
+    for (name, version) in [
+        ("../11/noise", "1.0"),
+        ("..", "1.0"),
+        ("a/b", "1.0"),
+        ("/tmp/noise", "1.0"),
+        ("Noise", ".."),
+        ("Noise", "../../11"),
+        ("Noise", "/"),
+        ("", "1.0"),
+    ] {
+        let ext = ExtensionRef {
+            name: name.to_string(),
+            url: url.to_string(),
+            version: version.to_string(),
+        };
+        let re = resolver.resolve(&ext);
+        assert!(re.is_err(), "{} {}", name, version);
+        assert!(re.unwrap_err().to_string().contains("invalid"));
+    }
+    //-- the last segment of the URL is not used, "{name}.ext.json" still is
+    let ext = ExtensionRef {
+        name: "noise".to_string(),
+        url: "https://someurl.org/..".to_string(),
+        version: "1.0".to_string(),
+    };
+    assert!(resolver.resolve(&ext).is_ok());
+}
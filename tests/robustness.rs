@@ -0,0 +1,210 @@
+//-- hostile or malformed inputs: an error is returned, never a panic
+
+use cjval::{CJValidator, Severity, ValidationOptions};
+use serde_json::json;
+use serde_json::Value;
+
+fn get_cube() -> Value {
+    let s = std::fs::read_to_string("data/cube.city.json").unwrap();
+    serde_json::from_str(&s).unwrap()
+}
+
+#[test]
+fn not_a_cityjson_object() {
+    for s in [
+        "",
+        "[]",
+        "12",
+        "null",
+        "\"CityJSON\"",
+        "{\"type\": \"CityJSON\"}",
+    ] {
+        let v = CJValidator::from_str(s);
+        assert!(v.get_extensions_urls().is_none());
+        assert!(v.get_extensions().is_empty());
+        assert!(v.is_empty_cityjson());
+        assert_eq!(v.get_cjseq_feature_id(), "");
+        assert!(!v.is_valid());
+    }
+}
+
+#[test]
+fn cjfeature_id_not_a_string() {
+    let s = std::fs::read_to_string("data/cube_20.jsonl").unwrap();
+    let mut lines = s.lines();
+    let mut v = CJValidator::from_str(lines.next().unwrap());
+    let mut f: Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    f["id"] = json!(12);
+    v.from_str_cjfeature(&f.to_string()).unwrap();
+    assert_eq!(v.get_cjseq_feature_id(), "12");
+    assert!(!v.validate()["schema"].is_valid());
+}
+
+#[test]
+fn index_not_a_usize() {
+    let mut j = get_cube();
+    *j.pointer_mut("/CityObjects/id-1/geometry/0/boundaries/0/0/0/0")
+        .unwrap() = json!(1e20);
+    let v = CJValidator::from_str(&j.to_string());
+    let re = v.validate();
+    assert!(re["schema"].is_valid());
+    assert!(!re["wrong_vertex_index"].is_valid());
+}
+
+#[test]
+fn material_without_appearance() {
+    let s = std::fs::read_to_string("data/material.city.json").unwrap();
+    let mut j: Value = serde_json::from_str(&s).unwrap();
+    j.as_object_mut().unwrap().remove("appearance");
+    let v = CJValidator::from_str(&j.to_string());
+    let re = v.validate();
+    assert!(!re["materials"].is_valid());
+}
+
+#[test]
+fn texture_without_appearance() {
+    let s = std::fs::read_to_string("data/texture1.city.json").unwrap();
+    let mut j: Value = serde_json::from_str(&s).unwrap();
+    j.as_object_mut().unwrap().remove("appearance");
+    let v = CJValidator::from_str(&j.to_string());
+    let re = v.validate();
+    assert!(!re["textures"].is_valid());
+}
+
+#[test]
+fn extension_not_an_object() {
+    let mut v = CJValidator::from_str(&get_cube().to_string());
+    assert!(v.add_one_extension_from_str("[1, 2]").is_err());
+    assert!(v.get_extension_definitions().is_empty());
+}
+
+#[test]
+fn extension_infinite_recursion() {
+    let mut j = get_cube();
+    j["+census"] = json!(12);
+    let jext = json!({
+      "type": "CityJSONExtension",
+      "name": "Census",
+      "uri": "https://someurl.org/census.ext.json",
+      "version": "1.0",
+      "versionCityJSON": "2.0",
+      "description": "",
+      "extraAttributes": {},
+      "extraCityObjects": {},
+      "extraSemanticSurfaces": {},
+      "extraRootProperties": {
+        "+census": {"allOf": [{"$ref": "#/definitions/a"}]}
+      },
+      "definitions": {
+        "a": {"not": {"$ref": "#/extraRootProperties/+census"}}
+      }
+    });
+    let mut v = CJValidator::from_str(&j.to_string());
+    v.add_one_extension_from_str(&jext.to_string()).unwrap();
+    let re = v.validate();
+    assert!(!re["extensions"].is_valid());
+    assert!(re["extensions"].get_errors()[0].contains("infinite recursion"));
+    let re = cjval::validate_extension_str(&jext.to_string());
+    assert_eq!(re.errors[0].check, "compile");
+}
+
+#[test]
+fn extension_schema_not_an_object() {
+    let mut j = get_cube();
+    j["+census"] = json!(12);
+    let jext = json!({
+      "type": "CityJSONExtension",
+      "name": "Census",
+      "uri": "https://someurl.org/census.ext.json",
+      "version": "1.0",
+      "versionCityJSON": "2.0",
+      "description": "",
+      "extraAttributes": {"Building": 5},
+      "extraCityObjects": {},
+      "extraSemanticSurfaces": {},
+      "extraRootProperties": {"+census": 12}
+    });
+    let mut v = CJValidator::from_str(&j.to_string());
+    v.add_one_extension_from_str(&jext.to_string()).unwrap();
+    assert!(!v.validate()["extensions"].is_valid());
+    assert!(!cjval::validate_extension_str(&jext.to_string()).is_valid());
+}
+
+fn validate_without_schema(s: &str, schema: Severity) {
+    let mut v = CJValidator::from_str(s);
+    let mut opts = ValidationOptions::new();
+    opts.set("schema", schema).unwrap();
+    opts.set_exhaustive(true);
+    v.set_options(opts);
+    v.validate();
+}
+
+#[test]
+fn schema_warning() {
+    //-- the checks run after a schema warning, with a file that is not valid
+    let s = r#"{"type": "CityJSON", "version": "2.0",
+        "transform": {"scale": [1, 1, 1], "translate": [0, 0, 0]},
+        "CityObjects": {"a": {"type": "Building", "children": "x"}}, "vertices": "x"}"#;
+    let mut v = CJValidator::from_str(s);
+    let mut opts = ValidationOptions::new();
+    opts.set("schema", Severity::Warning).unwrap();
+    v.set_options(opts);
+    let re = v.validate();
+    assert!(re["schema"].is_warning());
+    assert!(re["parents_children_consistency"].is_valid());
+    assert!(re["wrong_vertex_index"].is_valid());
+    validate_without_schema(s, Severity::Off);
+}
+
+//-- the pointers of the values, only the 1st elements of the arrays
+fn get_pointers(j: &Value, p: String, ps: &mut Vec<String>) {
+    match j {
+        Value::Object(o) => {
+            for (k, x) in o {
+                get_pointers(
+                    x,
+                    format!("{}/{}", p, k.replace('~', "~0").replace('/', "~1")),
+                    ps,
+                )
+            }
+        }
+        Value::Array(a) => {
+            for (i, x) in a.iter().enumerate().take(2) {
+                get_pointers(x, format!("{}/{}", p, i), ps)
+            }
+        }
+        _ => (),
+    }
+    ps.push(p);
+}
+
+#[test]
+fn schema_disabled() {
+    //-- the other checks cannot rely on the schema: each value is replaced
+    //-- by one of the wrong type
+    for f in [
+        "data/cube.city.json",
+        "data/material.city.json",
+        "data/sem.json",
+        "data/address.json",
+        "data/texture1.city.json",
+    ] {
+        let j: Value = serde_json::from_str(&std::fs::read_to_string(f).unwrap()).unwrap();
+        let mut ps: Vec<String> = Vec::new();
+        get_pointers(&j, String::new(), &mut ps);
+        ps.pop();
+        for p in &ps {
+            for x in [
+                json!("x"),
+                json!(-1),
+                json!(null),
+                json!({}),
+                json!([["x"]]),
+            ] {
+                let mut j2 = j.clone();
+                *j2.pointer_mut(p).unwrap() = x;
+                validate_without_schema(&j2.to_string(), Severity::Off);
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use cjval::CJValidator;
+use cjval::SchemaSet;
+use std::sync::Arc;
+
+#[test]
+fn shared_schemaset() {
+    let schemas = Arc::new(SchemaSet::new());
+    let sdata = std::fs::read_to_string("data/noise1.city.json").unwrap();
+    let sext = std::fs::read_to_string("schemas/extensions/11/noise.ext.json").unwrap();
+    let mut v = CJValidator::from_str_with_schemas(&sdata, schemas.clone());
+    let _ = v.add_one_extension_from_str(&sext);
+    let re = v.validate();
+    assert!(re["schema"].is_valid());
+    assert!(re["extensions"].is_valid());
+    let n = schemas.extension_cache_len();
+    assert!(n > 0);
+
+    //-- a 2nd validator reuses the compiled Extension schemas
+    let mut v2 = CJValidator::from_str_with_schemas(&sdata, schemas.clone());
+    let _ = v2.add_one_extension_from_str(&sext);
+    let re = v2.validate();
+    assert!(re["extensions"].is_valid());
+    assert_eq!(schemas.extension_cache_len(), n);
+}
+
+#[test]
+fn cjseq_features_share_schemas() {
+    let schemas = Arc::new(SchemaSet::new());
+    schemas.precompile();
+    let s = std::fs::read_to_string("data/cube_20.jsonl").unwrap();
+    let mut lines = s.lines();
+    let mut v = CJValidator::from_str_with_schemas(lines.next().unwrap(), schemas.clone());
+    assert!(v.validate()["schema"].is_valid());
+    for l in lines {
+        if v.from_str_cjfeature(l).is_ok() {
+            let _ = v.validate();
+        }
+    }
+    assert_eq!(v.get_cityjson_schema_version(), "2.0.2");
+}
+
+#[test]
+fn other_extension_other_schemas() {
+    let schemas = Arc::new(SchemaSet::new());
+    let sdata = std::fs::read_to_string("data/noise1.city.json").unwrap();
+    let sext = std::fs::read_to_string("schemas/extensions/11/noise.ext.json").unwrap();
+    let mut v = CJValidator::from_str_with_schemas(&sdata, schemas.clone());
+    let _ = v.add_one_extension_from_str(&sext);
+    assert!(v.validate()["extensions"].is_valid());
+    let n = schemas.extension_cache_len();
+    //-- the same Extension with another version is compiled again
+    let mut j: serde_json::Value = serde_json::from_str(&sext).unwrap();
+    j["version"] = serde_json::json!("9.9");
+    let mut v2 = CJValidator::from_str_with_schemas(&sdata, schemas.clone());
+    let _ = v2.add_one_extension_from_str(&j.to_string());
+    let _ = v2.validate();
+    assert_eq!(schemas.extension_cache_len(), 2 * n);
+}